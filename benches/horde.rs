@@ -0,0 +1,121 @@
+//! Times the horde navigation pipeline headless: `cargo bench --bench horde`.
+use std::time::{Duration, Instant};
+
+use alone::{
+    components::*,
+    level::Level,
+    navigation::{FlowField, NavGrid, NavigationPlugin},
+    prefabs,
+    resources::{ArchetypeConfig, GameRng, SteeringConfig},
+    states::{AppState, StatesPlugin},
+    systems::{movement, steering, targeting},
+};
+use bevy::prelude::*;
+
+const FRAMES: u32 = 300;
+
+fn build_app(enemies: u32) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin, NavigationPlugin))
+        .init_resource::<SteeringConfig>()
+        .init_resource::<GameRng>()
+        .add_systems(
+            Update,
+            (
+                targeting::acquire_target_system,
+                steering::steering_system,
+                movement::rotate_to_player_system,
+                movement::move_system,
+            )
+                .chain(),
+        );
+    app.world.insert_resource(NextState(Some(AppState::InGame)));
+
+    let level: Level = ron::de::from_str(include_str!("../assets/levels/arena.level.ron"))
+        .expect("arena level should parse");
+    for def in level.obstacles {
+        app.world.spawn((
+            TransformBundle::from_transform(Transform::from_translation(def.position.extend(0.0))),
+            Obstacle(def.shape),
+        ));
+    }
+
+    app.world.spawn((
+        TransformBundle::default(),
+        Player,
+        Targetable { threat: 1.0 },
+        Velocity::default(),
+    ));
+
+    let stats = ArchetypeConfig::default();
+    let archetypes = [
+        EnemyArchetype::Grunt,
+        EnemyArchetype::Hunter,
+        EnemyArchetype::Swarmer,
+    ];
+    for i in 0..enemies {
+        // spread the horde evenly over a ring around the arena
+        let angle = i as f32 / enemies as f32 * std::f32::consts::TAU;
+        let pos = Vec2::from_angle(angle) * Vec2::new(560.0, 290.0);
+        let archetype = archetypes[i as usize % archetypes.len()];
+        app.world.spawn((
+            TransformBundle::from_transform(Transform::from_translation(pos.extend(0.0))),
+            Enemy,
+            prefabs::enemy_archetype_bundle(archetype, stats.0[&archetype]),
+            Target::default(),
+            Steering::default(),
+            PathFollow::default(),
+            Velocity::default(),
+        ));
+    }
+    app
+}
+
+fn run(enemies: u32) {
+    let mut app = build_app(enemies);
+    let mut frames = Vec::with_capacity(FRAMES as usize);
+    for frame in 0..FRAMES {
+        // walk the player in a circle so the flow field keeps being recomputed
+        let angle = frame as f32 * 0.02;
+        let mut players = app.world.query_filtered::<&mut Transform, With<Player>>();
+        for mut t in players.iter_mut(&mut app.world) {
+            t.translation = (Vec2::from_angle(angle) * 150.0).extend(0.0);
+        }
+
+        let start = Instant::now();
+        app.update();
+        frames.push(start.elapsed());
+    }
+    frames.sort();
+    let mean = frames.iter().sum::<Duration>() / FRAMES;
+    let p95 = frames[(FRAMES as usize * 95) / 100];
+    println!(
+        "horde/{enemies}: mean {:.3} ms, p95 {:.3} ms, max {:.3} ms",
+        mean.as_secs_f64() * 1e3,
+        p95.as_secs_f64() * 1e3,
+        frames[frames.len() - 1].as_secs_f64() * 1e3,
+    );
+}
+
+fn flow_field_recompute() {
+    let mut app = build_app(0);
+    app.update();
+    let grid = app.world.resource::<NavGrid>();
+    let mut flow = FlowField::default();
+    let runs = 50;
+    let start = Instant::now();
+    for i in 0..runs {
+        flow.compute(grid, Vec2::new(i as f32 * 8.0 - 200.0, 0.0));
+    }
+    println!(
+        "flow_field/compute: mean {:.3} ms",
+        start.elapsed().as_secs_f64() * 1e3 / runs as f64
+    );
+}
+
+fn main() {
+    flow_field_recompute();
+    for enemies in [500, 2000, 4000] {
+        run(enemies);
+    }
+}
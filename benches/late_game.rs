@@ -0,0 +1,338 @@
+//! Plays late-game rounds headless at a fixed seed and reports frame and per-system timings as
+//! JSON, with entity pooling on and off, for a regular late-game build and one that churns
+//! through bullets and particles: `cargo bench --bench late_game [-- out.json]`.
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use alone::{
+    components::*,
+    diagnostics::{Metrics, MetricsPlugin},
+    events::GameEventsPlugin,
+    gameplay::GameplayPlugin,
+    instancing::InstancingPlugin,
+    level::Level,
+    materials::MyMaterialsPlugin,
+    meshes::MyMeshesPlugin,
+    navigation::NavigationPlugin,
+    particles::ParticlesPlugin,
+    prefabs::{EntityPools, PoolPlugin},
+    resources::*,
+    states::{AppState, StatesPlugin},
+    systems::rounds,
+};
+use bevy::{
+    audio::AudioPlugin,
+    prelude::*,
+    render::{settings::WgpuSettings, RenderPlugin},
+    time::TimeUpdateStrategy,
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+use serde::Serialize;
+
+const SEED: u64 = 7;
+const ROUNDS: [u32; 3] = [10, 20, 25];
+const FRAMES: usize = 600;
+/// Frames allowed for the telegraphs of a round to turn into enemies.
+const MAX_WARM_UP_FRAMES: u32 = 600;
+const FRAME_BUDGET_MS: f64 = 1000.0 / 60.0;
+
+/// The weapon the player plays the rounds with.
+struct Scenario {
+    name: &'static str,
+    /// Seconds between shots, zero fires every frame.
+    cooldown: f32,
+    bullets: u32,
+}
+
+const SCENARIOS: [Scenario; 2] = [
+    Scenario {
+        name: "late_game",
+        cooldown: 0.1,
+        bullets: 3,
+    },
+    // a full volley acquired and an older one released every frame, with their muzzle flashes
+    Scenario {
+        name: "churn",
+        cooldown: 0.0,
+        bullets: 12,
+    },
+];
+
+#[derive(Serialize)]
+struct Timings {
+    mean_ms: f64,
+    p50_ms: f64,
+    p95_ms: f64,
+    p99_ms: f64,
+    max_ms: f64,
+}
+
+impl Timings {
+    fn from_ms(mut samples: Vec<f64>) -> Self {
+        samples.sort_by(f64::total_cmp);
+        let at = |p: f64| samples[((samples.len() - 1) as f64 * p).round() as usize];
+        Self {
+            mean_ms: samples.iter().sum::<f64>() / samples.len() as f64,
+            p50_ms: at(0.5),
+            p95_ms: at(0.95),
+            p99_ms: at(0.99),
+            max_ms: samples[samples.len() - 1],
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RoundReport {
+    round: u32,
+    enemies_spawned: u32,
+    mean_enemies: f64,
+    mean_bullets: f64,
+    max_collision_pairs: u32,
+    frame: Timings,
+    holds_60fps: bool,
+    systems: BTreeMap<&'static str, Timings>,
+}
+
+#[derive(Serialize)]
+struct Run {
+    scenario: &'static str,
+    pooling: bool,
+    rounds: Vec<RoundReport>,
+}
+
+#[derive(Serialize)]
+struct Report {
+    seed: u64,
+    frames_per_round: usize,
+    frame_budget_ms: f64,
+    runs: Vec<Run>,
+}
+
+fn build_app(scenario: &Scenario, pooling: bool) -> App {
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            })
+            .set(RenderPlugin {
+                wgpu_settings: WgpuSettings {
+                    backends: None,
+                    ..default()
+                },
+            })
+            .disable::<WinitPlugin>()
+            .disable::<AudioPlugin>(),
+    )
+    .add_plugins((
+        StatesPlugin,
+        GameEventsPlugin,
+        MyMeshesPlugin,
+        MyMaterialsPlugin,
+        NavigationPlugin,
+        ParticlesPlugin,
+        MetricsPlugin,
+        PoolPlugin,
+        InstancingPlugin,
+        GameplayPlugin,
+    ))
+    // simulate at a steady 60 FPS however long the frames really take
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1.0 / 60.0,
+    )))
+    .insert_resource(GameRng::from_seed(SEED))
+    // the trigger stays pulled, the weapon cooldown sets the pace
+    .insert_resource(PlayerInput {
+        fire: true,
+        ..default()
+    })
+    .insert_resource(RoundParams {
+        round: 1,
+        length: RoundConfig::default().length,
+        countdown: 0.0,
+        enemies: 0,
+        phase: RoundPhase::Intermission,
+        cleared: false,
+        bonus: 0,
+    });
+    app.world.insert_resource(NextState(Some(AppState::InGame)));
+    app.world.resource_mut::<EntityPools>().enabled = pooling;
+
+    let level: Level = ron::de::from_str(include_str!("../assets/levels/arena.level.ron"))
+        .expect("arena level should parse");
+    app.insert_resource(Arena {
+        bounds: level.bounds,
+        player_spawn: level.player_spawn,
+        spawn_zones: level.spawn_zones.clone(),
+    });
+    for def in level.obstacles {
+        app.world.spawn((
+            TransformBundle::from_transform(Transform::from_translation(def.position.extend(0.0))),
+            Obstacle(def.shape),
+        ));
+    }
+
+    // a late-game build that can keep up with the horde, and cannot die. Without `Move` or an
+    // aim the game's own movement and aim leave it to `script_player`
+    app.world.spawn((
+        TransformBundle::default(),
+        Player,
+        Targetable { threat: 1.0 },
+        Health(f32::MAX),
+        Weapon {
+            cooldown: scenario.cooldown,
+            remains: 0.0,
+            bullets: scenario.bullets,
+            pierce: 1,
+            bullet_speed: 1000.0,
+            bullet_lifetime: 0.5,
+        },
+        HitCooldown {
+            time_full: 2.0,
+            time_remains: 0.0,
+        },
+        Velocity::default(),
+        Sensor { radius: 3.0 },
+    ));
+    app
+}
+
+/// Walks the player in a circle and aims at the nearest enemy.
+fn script_player(app: &mut App, frame: u32) {
+    let angle = frame as f32 * 0.02;
+    let pos = Vec2::from_angle(angle) * 150.0;
+    let nearest = app
+        .world
+        .query_filtered::<&Transform, With<Enemy>>()
+        .iter(&app.world)
+        .map(|t| t.translation.truncate())
+        .min_by(|a, b| a.distance_squared(pos).total_cmp(&b.distance_squared(pos)));
+    let mut players = app
+        .world
+        .query_filtered::<(&mut Transform, &mut Velocity), With<Player>>();
+    for (mut t, mut velocity) in players.iter_mut(&mut app.world) {
+        t.translation = pos.extend(0.0);
+        velocity.0 = Vec2::from_angle(angle).perp() * 150.0 * 0.02 * 60.0;
+        if let Some(dir) = nearest.and_then(|n| (n - pos).try_normalize()) {
+            t.rotation = Quat::from_rotation_arc_2d(Vec2::Y, dir);
+        }
+    }
+}
+
+/// Clears the arena and starts `target` right away, sized as if every round before it was played.
+fn fast_forward(app: &mut App, target: u32) {
+    let leftovers: Vec<Entity> = app
+        .world
+        .query_filtered::<Entity, Or<(With<Enemy>, With<Bullet>, With<SpawnTelegraph>)>>()
+        .iter(&app.world)
+        .collect();
+    for e in leftovers {
+        app.world.entity_mut(e).despawn_recursive();
+    }
+    let config = app.world.resource::<RoundConfig>().clone();
+    let mut round = RoundParams {
+        round: 1,
+        length: config.length,
+        countdown: 0.0,
+        enemies: config.first_enemies,
+        phase: RoundPhase::WarmUp,
+        cleared: false,
+        bonus: 0,
+    };
+    while round.round < target {
+        rounds::next_round(&mut round, &config);
+    }
+    round.countdown = 0.0;
+    app.insert_resource(round);
+    app.insert_resource(GameRng::from_seed(SEED));
+}
+
+fn run_round(app: &mut App, target: u32, frame: &mut u32) -> RoundReport {
+    fast_forward(app, target);
+    let enemies_spawned = app.world.resource::<RoundParams>().enemies;
+    for _ in 0..MAX_WARM_UP_FRAMES {
+        script_player(app, *frame);
+        app.update();
+        *frame += 1;
+        let telegraphs = app
+            .world
+            .query_filtered::<(), With<SpawnTelegraph>>()
+            .iter(&app.world)
+            .len();
+        if app.world.resource::<RoundParams>().phase == RoundPhase::Active && telegraphs == 0 {
+            break;
+        }
+    }
+
+    app.world.resource_mut::<Metrics>().clear_frames();
+    let mut frame_ms = Vec::with_capacity(FRAMES);
+    for _ in 0..FRAMES {
+        script_player(app, *frame);
+        let start = Instant::now();
+        app.update();
+        frame_ms.push(start.elapsed().as_secs_f64() * 1e3);
+        *frame += 1;
+    }
+
+    let metrics = app.world.resource::<Metrics>();
+    let frames = metrics.frames();
+    let mut samples: BTreeMap<&'static str, Vec<f64>> = BTreeMap::new();
+    for f in frames {
+        for (name, ms) in &f.systems {
+            samples.entry(*name).or_default().push(*ms);
+        }
+    }
+    let frame = Timings::from_ms(frame_ms);
+    RoundReport {
+        round: target,
+        enemies_spawned,
+        mean_enemies: frames.iter().map(|f| f.enemies as f64).sum::<f64>() / frames.len() as f64,
+        mean_bullets: frames.iter().map(|f| f.bullets as f64).sum::<f64>() / frames.len() as f64,
+        max_collision_pairs: frames.iter().map(|f| f.collision_pairs).max().unwrap_or(0),
+        holds_60fps: frame.p95_ms <= FRAME_BUDGET_MS,
+        frame,
+        systems: samples
+            .into_iter()
+            .map(|(name, ms)| (name, Timings::from_ms(ms)))
+            .collect(),
+    }
+}
+
+fn run(scenario: &Scenario, pooling: bool) -> Run {
+    let mut app = build_app(scenario, pooling);
+    app.world.resource_mut::<Metrics>().record = true;
+    // let the startup systems run and the state switch to the game
+    app.update();
+    let mut frame = 0;
+    Run {
+        scenario: scenario.name,
+        pooling,
+        rounds: ROUNDS
+            .iter()
+            .map(|&round| run_round(&mut app, round, &mut frame))
+            .collect(),
+    }
+}
+
+fn main() {
+    let report = Report {
+        seed: SEED,
+        frames_per_round: FRAMES,
+        frame_budget_ms: FRAME_BUDGET_MS,
+        runs: SCENARIOS
+            .iter()
+            .flat_map(|scenario| [run(scenario, true), run(scenario, false)])
+            .collect(),
+    };
+    let json = serde_json::to_string_pretty(&report).expect("report should serialize");
+    println!("{json}");
+    // cargo passes its own flags along, the first plain argument is the output file
+    if let Some(path) = std::env::args().skip(1).find(|a| !a.starts_with('-')) {
+        std::fs::write(&path, json).expect("report should be writable");
+    }
+}
@@ -0,0 +1,296 @@
+use bevy::{audio::Volume, prelude::*, utils::HashMap};
+use rand::Rng;
+
+use crate::{
+    events::{
+        ButtonHovered, ButtonPressed, EnemyKilled, PlayerDied, PlayerHit, RoundEnded, RoundStarted,
+        ShotFired,
+    },
+    states::AppState,
+};
+
+const CROSSFADE_SECONDS: f32 = 1.5;
+/// Sound effects despawn once played, this drops the ones no audio device ever started.
+const SFX_LIFETIME: f32 = 2.0;
+
+/// Volume of each category, all multiplied by `master`.
+#[derive(Resource)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub sfx: f32,
+    pub music: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master: 0.8,
+            sfx: 1.0,
+            music: 0.5,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Sfx {
+    Shot,
+    EnemyKilled,
+    PlayerHit,
+    PlayerDied,
+    RoundStart,
+    RoundClear,
+    GameOver,
+    ButtonHover,
+    ButtonPress,
+}
+
+/// How a sound effect is played.
+pub struct SfxSpec {
+    pub path: &'static str,
+    pub volume: f32,
+    /// Playback speed is picked within `1.0 ± pitch_variation`.
+    pub pitch_variation: f32,
+    /// Instances allowed to play at once, extra requests are dropped.
+    pub max_concurrent: usize,
+}
+
+impl Sfx {
+    const ALL: [Sfx; 9] = [
+        Sfx::Shot,
+        Sfx::EnemyKilled,
+        Sfx::PlayerHit,
+        Sfx::PlayerDied,
+        Sfx::RoundStart,
+        Sfx::RoundClear,
+        Sfx::GameOver,
+        Sfx::ButtonHover,
+        Sfx::ButtonPress,
+    ];
+
+    pub fn spec(self) -> SfxSpec {
+        let (path, volume, pitch_variation, max_concurrent) = match self {
+            Sfx::Shot => ("audio/shot.wav", 0.4, 0.1, 4),
+            Sfx::EnemyKilled => ("audio/enemy_killed.wav", 0.5, 0.15, 6),
+            Sfx::PlayerHit => ("audio/player_hit.wav", 0.8, 0.05, 2),
+            Sfx::PlayerDied => ("audio/player_died.wav", 1.0, 0.0, 1),
+            Sfx::RoundStart => ("audio/round_start.wav", 0.7, 0.0, 1),
+            Sfx::RoundClear => ("audio/round_clear.wav", 0.7, 0.0, 1),
+            Sfx::GameOver => ("audio/game_over.wav", 0.9, 0.0, 1),
+            Sfx::ButtonHover => ("audio/ui_hover.wav", 0.3, 0.05, 2),
+            Sfx::ButtonPress => ("audio/ui_press.wav", 0.5, 0.05, 2),
+        };
+        SfxSpec {
+            path,
+            volume,
+            pitch_variation,
+            max_concurrent,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MusicTrack {
+    Menu,
+    Game,
+}
+
+impl MusicTrack {
+    fn path(self) -> &'static str {
+        match self {
+            MusicTrack::Menu => "audio/menu_music.wav",
+            MusicTrack::Game => "audio/game_music.wav",
+        }
+    }
+
+    fn for_state(state: &AppState) -> MusicTrack {
+        match state {
+            AppState::InGame => MusicTrack::Game,
+            AppState::Menu | AppState::GameOver | AppState::Editor | AppState::Shop => {
+                MusicTrack::Menu
+            }
+        }
+    }
+}
+
+/// Request to play a sound effect, anything can send it.
+#[derive(Event)]
+pub struct PlaySfx(pub Sfx);
+
+#[derive(Resource)]
+struct AudioHandles {
+    sfx: HashMap<Sfx, Handle<AudioSource>>,
+    music: HashMap<MusicTrack, Handle<AudioSource>>,
+}
+
+#[derive(Component)]
+struct SfxInstance {
+    sfx: Sfx,
+    remains: f32,
+}
+
+/// A playing music track. `fade` goes from 0 to 1 while fading in and back while fading out.
+#[derive(Component)]
+struct Music {
+    track: MusicTrack,
+    fade: f32,
+    fading_out: bool,
+}
+
+fn load_audio(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let sfx = Sfx::ALL
+        .iter()
+        .map(|s| (*s, asset_server.load(s.spec().path)))
+        .collect();
+    let music = [MusicTrack::Menu, MusicTrack::Game]
+        .iter()
+        .map(|m| (*m, asset_server.load(m.path())))
+        .collect();
+    commands.insert_resource(AudioHandles { sfx, music });
+}
+
+/// Turns gameplay events into sound effect requests.
+#[allow(clippy::too_many_arguments)]
+fn gameplay_sounds(
+    mut shots: EventReader<ShotFired>,
+    mut kills: EventReader<EnemyKilled>,
+    mut hits: EventReader<PlayerHit>,
+    mut deaths: EventReader<PlayerDied>,
+    mut rounds: EventReader<RoundStarted>,
+    mut ended: EventReader<RoundEnded>,
+    mut hovered: EventReader<ButtonHovered>,
+    mut pressed: EventReader<ButtonPressed>,
+    mut play: EventWriter<PlaySfx>,
+) {
+    let requests = [
+        (Sfx::Shot, shots.iter().count()),
+        (Sfx::EnemyKilled, kills.iter().count()),
+        (Sfx::PlayerHit, hits.iter().count()),
+        (Sfx::PlayerDied, deaths.iter().count()),
+        (Sfx::RoundStart, rounds.iter().count()),
+        (Sfx::RoundClear, ended.iter().filter(|e| e.cleared).count()),
+        (Sfx::ButtonHover, hovered.iter().count()),
+        (Sfx::ButtonPress, pressed.iter().count()),
+    ];
+    for (sfx, count) in requests {
+        play.send_batch(std::iter::repeat_with(|| PlaySfx(sfx)).take(count));
+    }
+}
+
+fn game_over_sound(mut play: EventWriter<PlaySfx>) {
+    play.send(PlaySfx(Sfx::GameOver));
+}
+
+fn play_sfx(
+    mut commands: Commands,
+    mut requests: EventReader<PlaySfx>,
+    settings: Res<AudioSettings>,
+    handles: Res<AudioHandles>,
+    playing: Query<&SfxInstance>,
+    mut counts: Local<HashMap<Sfx, usize>>,
+) {
+    counts.clear();
+    for instance in &playing {
+        *counts.entry(instance.sfx).or_default() += 1;
+    }
+    let mut rng = rand::thread_rng();
+    for PlaySfx(sfx) in requests.iter() {
+        let spec = sfx.spec();
+        let count = counts.entry(*sfx).or_default();
+        if *count >= spec.max_concurrent {
+            continue;
+        }
+        *count += 1;
+        let volume = spec.volume * settings.sfx * settings.master;
+        let speed = 1.0 + rng.gen_range(-1.0..=1.0) * spec.pitch_variation;
+        commands.spawn((
+            AudioBundle {
+                source: handles.sfx[sfx].clone(),
+                settings: PlaybackSettings::DESPAWN
+                    .with_volume(Volume::new_relative(volume))
+                    .with_speed(speed),
+            },
+            SfxInstance {
+                sfx: *sfx,
+                remains: SFX_LIFETIME,
+            },
+        ));
+    }
+}
+
+fn expire_sfx(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut SfxInstance), Without<AudioSink>>,
+    time: Res<Time>,
+) {
+    for (e, mut instance) in &mut query {
+        instance.remains -= time.delta_seconds();
+        if instance.remains <= 0.0 {
+            commands.entity(e).despawn();
+        }
+    }
+}
+
+/// Starts the track of the current state and crossfades away from the old one.
+/// Fading is tracked on `Music` so it completes even when there is no sink to play to.
+fn update_music(
+    mut commands: Commands,
+    state: Res<State<AppState>>,
+    settings: Res<AudioSettings>,
+    handles: Res<AudioHandles>,
+    mut query: Query<(Entity, &mut Music, Option<&AudioSink>)>,
+    time: Res<Time>,
+) {
+    let wanted = MusicTrack::for_state(state.get());
+    let mut found = false;
+    for (_, mut music, _) in &mut query {
+        if music.track == wanted && !music.fading_out {
+            found = true;
+        } else {
+            music.fading_out = true;
+        }
+    }
+    if !found {
+        commands.spawn((
+            AudioBundle {
+                source: handles.music[&wanted].clone(),
+                settings: PlaybackSettings::LOOP.with_volume(Volume::new_relative(0.0)),
+            },
+            Music {
+                track: wanted,
+                fade: 0.0,
+                fading_out: false,
+            },
+        ));
+    }
+
+    let step = time.delta_seconds() / CROSSFADE_SECONDS;
+    for (e, mut music, sink) in &mut query {
+        music.fade = match music.fading_out {
+            true => music.fade - step,
+            false => music.fade + step,
+        }
+        .clamp(0.0, 1.0);
+        if music.fading_out && music.fade <= 0.0 {
+            commands.entity(e).despawn();
+            continue;
+        }
+        if let Some(sink) = sink {
+            sink.set_volume(music.fade * settings.music * settings.master);
+        }
+    }
+}
+
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AudioSettings>()
+            .add_event::<PlaySfx>()
+            .add_systems(Startup, load_audio)
+            .add_systems(OnEnter(AppState::GameOver), game_over_sound)
+            .add_systems(
+                Update,
+                (gameplay_sounds, play_sfx, expire_sfx, update_music).chain(),
+            );
+    }
+}
@@ -0,0 +1,311 @@
+use std::{collections::BTreeMap, str::FromStr, time::Duration};
+
+use bevy::{
+    app::{AppExit, ScheduleRunnerPlugin},
+    prelude::*,
+    time::TimeUpdateStrategy,
+};
+
+use crate::{
+    components::*,
+    resources::{Arena, PlayerInput, RoundParams, RunSeed, Score},
+    states::{AppState, PlayerInputSet},
+};
+
+/// Enemies closer than this are run from by the kiting bot.
+const KITE_DISTANCE: f32 = 180.0;
+/// Distance from the arena center the strafing bot circles at, relative to the arena size.
+const STRAFE_RADIUS: f32 = 0.3;
+/// Runs past this round count as survived and end the game.
+const MAX_ROUND: u32 = 50;
+/// How fast the bot clicks, about what a human manages.
+const CLICKS_PER_SECOND: f32 = 6.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BotStrategy {
+    /// Backs away from the nearby horde and shoots the nearest enemy.
+    Kite,
+    /// Circles the arena center and shoots the nearest enemy.
+    CircleStrafe,
+    /// Stands its ground and shoots the nearest enemy.
+    FocusNearest,
+}
+
+impl BotStrategy {
+    pub const ALL: [BotStrategy; 3] = [Self::Kite, Self::CircleStrafe, Self::FocusNearest];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Kite => "kite",
+            Self::CircleStrafe => "strafe",
+            Self::FocusNearest => "nearest",
+        }
+    }
+
+    /// Where to walk, given the player and enemy positions.
+    fn movement(self, pos: Vec2, enemies: &[Vec2], arena: &Arena) -> Vec2 {
+        match self {
+            Self::Kite => {
+                let away: Vec2 = enemies
+                    .iter()
+                    .map(|e| pos - *e)
+                    .filter(|d| d.length() < KITE_DISTANCE)
+                    .map(|d| d / d.length_squared().max(1.0))
+                    .sum();
+                // lean towards the center so the horde does not pin us to a wall
+                let half = arena.bounds / 2.0;
+                let to_center = -pos / half * 0.5 / KITE_DISTANCE;
+                (away + to_center).normalize_or_zero()
+            }
+            Self::CircleStrafe => {
+                let radius = arena.bounds.min_element() * STRAFE_RADIUS;
+                let out = pos.try_normalize().unwrap_or(Vec2::X);
+                let correction = out * (radius - pos.length()) / radius;
+                (out.perp() + correction).normalize_or_zero()
+            }
+            Self::FocusNearest => Vec2::ZERO,
+        }
+    }
+}
+
+impl FromStr for BotStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|strategy| strategy.name() == s)
+            .ok_or_else(|| format!("unknown bot {s:?}, expected kite, strafe or nearest"))
+    }
+}
+
+/// Plays in place of the keyboard and mouse while present.
+#[derive(Resource)]
+pub struct Bot(pub BotStrategy);
+
+fn bot_system(
+    bot: Res<Bot>,
+    arena: Res<Arena>,
+    mut input: ResMut<PlayerInput>,
+    player: Query<&Transform, (With<Player>, Without<Dead>)>,
+    enemies: Query<&Transform, With<Enemy>>,
+    mut since_click: Local<f32>,
+    time: Res<Time>,
+) {
+    *input = PlayerInput {
+        // the first upgrade on offer, whatever it is
+        pick: Some(0),
+        ..default()
+    };
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let pos = player.translation.truncate();
+    let enemies: Vec<Vec2> = enemies.iter().map(|t| t.translation.truncate()).collect();
+    let nearest = enemies
+        .iter()
+        .copied()
+        .min_by(|a, b| a.distance_squared(pos).total_cmp(&b.distance_squared(pos)));
+    input.movement = bot.0.movement(pos, &enemies, &arena);
+    input.aim = nearest;
+    // clicks like a player would, firing is not held down
+    *since_click += time.delta_seconds();
+    if nearest.is_some() && *since_click >= 1.0 / CLICKS_PER_SECOND {
+        input.fire = true;
+        *since_click = 0.0;
+    }
+}
+
+pub struct BotPlugin;
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerInput>().add_systems(
+            Update,
+            bot_system
+                .in_set(PlayerInputSet)
+                .run_if(in_state(AppState::InGame).and_then(resource_exists::<Bot>())),
+        );
+    }
+}
+
+/// Plays `games` seeded games headless with each strategy and prints how many rounds the bot
+/// survived. Started with `--batch <games> [--bot <strategy|all>] [--seed <n>]`.
+pub struct BatchPlugin {
+    pub games: u32,
+    pub strategies: Vec<BotStrategy>,
+    pub seed: u64,
+}
+
+impl BatchPlugin {
+    /// `None` without `--batch`, so the game starts as usual.
+    pub fn from_args() -> Option<Result<Self, String>> {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        args.iter()
+            .any(|a| a == "--batch")
+            .then(|| Self::parse(&args))
+    }
+
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let value = |flag: &str| match args.iter().position(|a| a == flag) {
+            Some(i) => args
+                .get(i + 1)
+                .map(|v| Some(v.as_str()))
+                .ok_or_else(|| format!("{flag} needs a value")),
+            None => Ok(None),
+        };
+        let games: u32 = value("--batch")?
+            .unwrap_or_default()
+            .parse()
+            .map_err(|e| format!("--batch: {e}"))?;
+        if games == 0 {
+            return Err("--batch needs at least one game".to_string());
+        }
+        let strategies = match value("--bot")? {
+            None => vec![BotStrategy::Kite],
+            Some("all") => BotStrategy::ALL.to_vec(),
+            Some(name) => vec![name.parse()?],
+        };
+        let seed = match value("--seed")? {
+            Some(seed) => seed.parse().map_err(|e| format!("--seed: {e}"))?,
+            None => 1,
+        };
+        Ok(Self {
+            games,
+            strategies,
+            seed,
+        })
+    }
+}
+
+#[derive(Resource)]
+struct Batch {
+    games: u32,
+    strategies: Vec<BotStrategy>,
+    first_seed: u64,
+    /// Rounds survived before dying, per strategy.
+    results: Vec<Vec<u32>>,
+    /// Games that lasted past `MAX_ROUND`, per strategy.
+    capped: Vec<u32>,
+    scores: Vec<Vec<u32>>,
+}
+
+fn start_game(mut next_state: ResMut<NextState<AppState>>) {
+    next_state.set(AppState::InGame);
+}
+
+/// Ends a game that has gone on long enough to count as won.
+fn cap_game(round: Res<RoundParams>, mut next_state: ResMut<NextState<AppState>>) {
+    if round.round > MAX_ROUND {
+        next_state.set(AppState::GameOver);
+    }
+}
+
+fn record_game(
+    mut commands: Commands,
+    mut batch: ResMut<Batch>,
+    mut seed: ResMut<RunSeed>,
+    round: Res<RoundParams>,
+    score: Res<Score>,
+    player: Query<Entity, With<Player>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    // a capped game leaves its player behind
+    for e in &player {
+        commands.entity(e).despawn_recursive();
+    }
+    let i = batch.results.len() - 1;
+    match round.round > MAX_ROUND {
+        true => batch.capped[i] += 1,
+        // the round the player died in does not count
+        false => batch.results[i].push(round.round - 1),
+    }
+    batch.scores[i].push(score.0);
+    seed.0 += 1;
+    if batch.scores[i].len() < batch.games as usize {
+        return;
+    }
+    if i + 1 < batch.strategies.len() {
+        // the next strategy plays the same seeds
+        batch.results.push(Vec::new());
+        batch.capped.push(0);
+        batch.scores.push(Vec::new());
+        seed.0 = batch.first_seed;
+        let strategy = batch.strategies[i + 1];
+        commands.insert_resource(Bot(strategy));
+        return;
+    }
+    report(&batch);
+    exit.send(AppExit);
+}
+
+fn report(batch: &Batch) {
+    println!(
+        "batch: {} games per bot, seeds {}..{}",
+        batch.games,
+        batch.first_seed,
+        batch.first_seed + batch.games as u64
+    );
+    for (((strategy, rounds), capped), scores) in batch
+        .strategies
+        .iter()
+        .zip(&batch.results)
+        .zip(&batch.capped)
+        .zip(&batch.scores)
+    {
+        let mean_score = scores.iter().sum::<u32>() as f32 / scores.len() as f32;
+        println!("\n{}, mean score {mean_score:.0}", strategy.name());
+        println!("still alive after round {MAX_ROUND}: {capped} games");
+        if rounds.is_empty() {
+            continue;
+        }
+        let mut sorted = rounds.clone();
+        sorted.sort_unstable();
+        let at = |p: f32| sorted[((sorted.len() - 1) as f32 * p).round() as usize];
+        let mean = sorted.iter().sum::<u32>() as f32 / sorted.len() as f32;
+        println!(
+            "survived rounds: min {} p10 {} median {} mean {mean:.1} p90 {} max {}",
+            sorted[0],
+            at(0.1),
+            at(0.5),
+            at(0.9),
+            sorted[sorted.len() - 1],
+        );
+        let mut histogram = BTreeMap::new();
+        for round in rounds {
+            *histogram.entry(*round).or_insert(0) += 1;
+        }
+        for (round, count) in histogram {
+            println!("  round {round:>3}: {count:>4} {}", "#".repeat(count));
+        }
+    }
+}
+
+impl Plugin for BatchPlugin {
+    fn build(&self, app: &mut App) {
+        let strategy = self.strategies[0];
+        app.add_plugins(ScheduleRunnerPlugin::run_loop(Duration::ZERO))
+            // a steady 60 FPS of game time, as fast as the machine allows
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+                1.0 / 60.0,
+            )))
+            .insert_resource(Batch {
+                games: self.games,
+                strategies: self.strategies.clone(),
+                first_seed: self.seed,
+                results: vec![Vec::new()],
+                capped: vec![0],
+                scores: vec![Vec::new()],
+            })
+            .insert_resource(RunSeed(self.seed))
+            .insert_resource(Bot(strategy))
+            .add_systems(OnEnter(AppState::GameOver), record_game)
+            .add_systems(
+                Update,
+                (
+                    start_game.run_if(not(in_state(AppState::InGame))),
+                    cap_game.run_if(in_state(AppState::InGame)),
+                ),
+            );
+    }
+}
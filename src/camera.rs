@@ -0,0 +1,234 @@
+use bevy::{prelude::*, render::camera::ScalingMode};
+
+use crate::{
+    components::{Dead, Enemy, Player},
+    events::{EnemyKilled, PlayerHit},
+    resources::{Arena, MouseWorldPos},
+    states::{AppState, PlayerInputSet},
+};
+
+/// How much of the world the camera shows.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CameraFit {
+    /// One world unit per logical pixel, the camera follows the player.
+    Window,
+    /// Always show the whole arena, letterboxed to the window.
+    Arena,
+}
+
+#[derive(Resource)]
+pub struct CameraSettings {
+    pub fit: CameraFit,
+    /// Multiplier on the projection scale, below 1.0 zooms in.
+    pub zoom: f32,
+    /// Fraction of the distance to the player covered per second.
+    pub follow_speed: f32,
+    pub max_shake_offset: f32,
+    pub max_shake_angle: f32,
+    /// Trauma lost per second.
+    pub trauma_decay: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            fit: CameraFit::Window,
+            zoom: 1.0,
+            follow_speed: 5.0,
+            max_shake_offset: 12.0,
+            max_shake_angle: 0.05,
+            trauma_decay: 1.5,
+        }
+    }
+}
+
+/// The gameplay camera. `position` is where it looks before shake is added on top.
+#[derive(Component, Default)]
+pub struct MainCamera {
+    pub position: Vec2,
+    pub trauma: f32,
+}
+
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn((Camera2dBundle::default(), MainCamera::default()));
+}
+
+fn reset_camera(mut query: Query<(&mut MainCamera, &mut Transform)>) {
+    for (mut camera, mut transform) in &mut query {
+        *camera = MainCamera::default();
+        transform.translation = Vec2::ZERO.extend(transform.translation.z);
+        transform.rotation = Quat::IDENTITY;
+    }
+}
+
+fn zoom_input(keyboard_input: Res<Input<KeyCode>>, mut settings: ResMut<CameraSettings>) {
+    if keyboard_input.just_pressed(KeyCode::Equals) {
+        settings.zoom = (settings.zoom * 0.8).max(0.25);
+    }
+    if keyboard_input.just_pressed(KeyCode::Minus) {
+        settings.zoom = (settings.zoom * 1.25).min(4.0);
+    }
+    if keyboard_input.just_pressed(KeyCode::F) {
+        settings.fit = match settings.fit {
+            CameraFit::Window => CameraFit::Arena,
+            CameraFit::Arena => CameraFit::Window,
+        };
+    }
+}
+
+fn apply_projection(
+    settings: Res<CameraSettings>,
+    arena: Res<Arena>,
+    mut query: Query<&mut OrthographicProjection, With<MainCamera>>,
+) {
+    if !settings.is_changed() && !arena.is_changed() {
+        return;
+    }
+    for mut projection in &mut query {
+        projection.scaling_mode = match settings.fit {
+            CameraFit::Window => ScalingMode::WindowSize(1.0),
+            CameraFit::Arena => ScalingMode::AutoMin {
+                min_width: arena.bounds.x,
+                min_height: arena.bounds.y,
+            },
+        };
+        projection.scale = settings.zoom;
+    }
+}
+
+fn add_trauma(
+    mut hits: EventReader<PlayerHit>,
+    mut kills: EventReader<EnemyKilled>,
+    mut query: Query<&mut MainCamera>,
+) {
+    let trauma = hits.iter().count() as f32 * 0.5 + kills.iter().count() as f32 * 0.1;
+    if trauma > 0.0 {
+        for mut camera in &mut query {
+            camera.trauma = (camera.trauma + trauma).min(1.0);
+        }
+    }
+}
+
+/// Smoothly follows the player, keeps the view inside the arena and adds trauma based shake.
+fn follow_player(
+    settings: Res<CameraSettings>,
+    arena: Res<Arena>,
+    player: Query<&Transform, (With<Player>, Without<Dead>, Without<MainCamera>)>,
+    mut query: Query<(&mut MainCamera, &mut Transform, &OrthographicProjection)>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    for (mut camera, mut transform, projection) in &mut query {
+        if let Ok(p) = player.get_single() {
+            let t = (settings.follow_speed * dt).min(1.0);
+            camera.position = camera.position.lerp(p.translation.truncate(), t);
+        }
+
+        // centre on an axis where the view is wider than the arena
+        let view_half = projection.area.size() / 2.0;
+        let room = (arena.bounds / 2.0 - view_half).max(Vec2::ZERO);
+        camera.position = camera.position.clamp(-room, room);
+
+        // shake grows with the square of trauma, driven by smooth pseudo noise
+        let shake = camera.trauma * camera.trauma;
+        let s = time.elapsed_seconds();
+        let noise = |freq: f32, phase: f32| (s * freq + phase).sin() * (s * freq * 0.37).cos();
+        let offset = Vec2::new(noise(31.0, 0.0), noise(29.0, 1.7)) * settings.max_shake_offset;
+        transform.translation = (camera.position + offset * shake).extend(transform.translation.z);
+        transform.rotation =
+            Quat::from_rotation_z(noise(23.0, 3.1) * settings.max_shake_angle * shake);
+        camera.trauma = (camera.trauma - settings.trauma_decay * dt).max(0.0);
+    }
+}
+
+/// Converts the cursor to world space every frame, so the camera moving under a still mouse
+/// keeps the position up to date. The shake is left out to keep aiming steady.
+pub fn cursor_to_world(
+    q_windows: Query<&Window>,
+    query: Query<(&Camera, &GlobalTransform, &MainCamera)>,
+    mut ms_world_pos: ResMut<MouseWorldPos>,
+) {
+    let window = q_windows.single();
+    if let Some(cursor) = window.cursor_position() {
+        let (camera, global_transf, main_camera) = query.single();
+        let steady = GlobalTransform::from(Transform::from_translation(
+            main_camera.position.extend(global_transf.translation().z),
+        ));
+        let world_pos = camera.viewport_to_world_2d(&steady, cursor);
+        if let Some(pos) = world_pos {
+            ms_world_pos.0 = pos;
+        }
+    }
+}
+
+/// Points an arrow from the edge of the view towards every enemy outside of it.
+fn offscreen_indicators(
+    mut gizmos: Gizmos,
+    camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+    enemies: Query<&Transform, With<Enemy>>,
+) {
+    const MARGIN: f32 = 14.0;
+    const SIZE: f32 = 6.0;
+    let Ok((camera_t, projection)) = camera.get_single() else {
+        return;
+    };
+    let center = camera_t.translation.truncate();
+    let half = projection.area.size() / 2.0;
+    let inset = (half - Vec2::splat(MARGIN)).max(Vec2::splat(1.0));
+    for t in &enemies {
+        let offset = t.translation.truncate() - center;
+        if offset.abs().cmple(half).all() {
+            continue;
+        }
+        // scale the offset down until it touches the inset view rectangle
+        let scale = (inset / offset.abs()).min_element();
+        let edge = center + offset * scale;
+        let dir = offset.normalize();
+        // fade arrows out for enemies far beyond the edge
+        let alpha = (1.0 - (offset.length() - half.length()) / half.length()).clamp(0.3, 1.0);
+        let tip = edge + dir * SIZE;
+        let base = edge - dir * SIZE;
+        let side = dir.perp() * SIZE * 0.7;
+        gizmos.linestrip_2d(
+            [tip, base + side, base - side, tip],
+            Color::rgba(1.0, 0.3, 0.3, alpha),
+        );
+    }
+}
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraSettings>()
+            .add_systems(Startup, spawn_camera)
+            .add_systems(OnEnter(AppState::InGame), reset_camera)
+            .add_systems(OnEnter(AppState::Editor), reset_camera)
+            .add_systems(
+                Update,
+                (zoom_input, apply_projection)
+                    .chain()
+                    .run_if(in_state(AppState::InGame).or_else(in_state(AppState::Editor))),
+            )
+            .add_systems(
+                Update,
+                (add_trauma, follow_player)
+                    .chain()
+                    .after(apply_projection)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                Update,
+                offscreen_indicators
+                    .after(follow_player)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                Update,
+                cursor_to_world
+                    .after(follow_player)
+                    .before(PlayerInputSet)
+                    .run_if(in_state(AppState::InGame).or_else(in_state(AppState::Editor))),
+            );
+    }
+}
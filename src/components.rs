@@ -1,43 +1,283 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
+use bevy_inspector_egui::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Component)]
+#[derive(Component, Reflect, InspectorOptions, Default)]
+#[reflect(Component, Default, InspectorOptions)]
 pub struct Sensor {
+    #[inspector(min = 0.0, max = 50.0)]
     pub radius: f32,
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
 pub struct Dead;
 
-#[derive(Component)]
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
 pub struct Player;
 
-#[derive(Component)]
+/// Cheat that keeps enemies from hurting the player.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
+pub struct God;
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
 pub struct Enemy;
 
-#[derive(Component)]
+#[derive(Component, Reflect, InspectorOptions, Default)]
+#[reflect(Component, Default, InspectorOptions)]
 pub struct HitCooldown {
+    #[inspector(min = 0.0, max = 10.0)]
     pub time_full: f32,
+    #[inspector(min = 0.0)]
     pub time_remains: f32,
 }
 
-#[derive(Component)]
-pub struct Health(pub f32);
+#[derive(Component, Reflect, InspectorOptions, Default)]
+#[reflect(Component, Default, InspectorOptions)]
+pub struct Health(#[inspector(min = 0.0)] pub f32);
+
+#[derive(Component, Reflect, InspectorOptions, Default)]
+#[reflect(Component, Default, InspectorOptions)]
+pub struct MaxHealth(#[inspector(min = 1.0)] pub f32);
+
+/// Time between shots, `remains` counts down to the next one.
+#[derive(Component, Reflect, InspectorOptions, Default)]
+#[reflect(Component, Default, InspectorOptions)]
+pub struct Weapon {
+    #[inspector(min = 0.01, max = 2.0)]
+    pub cooldown: f32,
+    #[inspector(min = 0.0)]
+    pub remains: f32,
+    /// Bullets fired per shot, fanned out around the aim.
+    #[inspector(min = 1, max = 16)]
+    pub bullets: u32,
+    #[inspector(max = 10)]
+    pub pierce: u32,
+    #[inspector(min = 0.0, max = 3000.0)]
+    pub bullet_speed: f32,
+    #[inspector(min = 0.05, max = 5.0)]
+    pub bullet_lifetime: f32,
+}
+
+/// Enemies a bullet can pass through before it is used up.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
+pub struct Pierce(pub u32);
+
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Stat {
+    MoveSpeed,
+    MaxHealth,
+    /// Shots per second.
+    FireRate,
+    BulletCount,
+    BulletPierce,
+    BulletSpeed,
+    BulletLifetime,
+    /// Seconds of invulnerability after a hit.
+    HitCooldown,
+}
+
+#[derive(Reflect, Clone, Copy, PartialEq, Debug)]
+pub enum ModifierOp {
+    Add(f32),
+    Mul(f32),
+}
+
+#[derive(Reflect, Clone, Copy, PartialEq, Debug)]
+pub struct Modifier {
+    pub stat: Stat,
+    pub op: ModifierOp,
+}
 
-#[derive(Component)]
+/// Base player stats and the modifiers stacked on top of them during a run.
+#[derive(Component, Reflect, Clone)]
+#[reflect(Component, Default)]
+pub struct PlayerStats {
+    pub base: HashMap<Stat, f32>,
+    pub modifiers: Vec<Modifier>,
+}
+
+impl Default for PlayerStats {
+    fn default() -> Self {
+        let base = [
+            (Stat::MoveSpeed, 100.0),
+            (Stat::MaxHealth, 5.0),
+            (Stat::FireRate, 1.0 / 0.15),
+            (Stat::BulletCount, 1.0),
+            (Stat::BulletPierce, 0.0),
+            (Stat::BulletSpeed, 1000.0),
+            (Stat::BulletLifetime, 0.5),
+            (Stat::HitCooldown, 2.0),
+        ];
+        Self {
+            base: base.into_iter().collect(),
+            modifiers: Vec::new(),
+        }
+    }
+}
+
+impl PlayerStats {
+    /// Base plus every additive modifier, then scaled by every multiplier, so the order the
+    /// modifiers were picked in does not matter.
+    pub fn get(&self, stat: Stat) -> f32 {
+        let mut add = 0.0;
+        let mut mul = 1.0;
+        for m in self.modifiers.iter().filter(|m| m.stat == stat) {
+            match m.op {
+                ModifierOp::Add(v) => add += v,
+                ModifierOp::Mul(v) => mul *= v,
+            }
+        }
+        let value = (self.base.get(&stat).copied().unwrap_or(0.0) + add) * mul;
+        match stat {
+            Stat::MaxHealth | Stat::BulletCount => value.max(1.0),
+            Stat::FireRate => value.max(0.5),
+            _ => value.max(0.0),
+        }
+    }
+}
+
+#[derive(Component, Reflect, InspectorOptions, Default)]
+#[reflect(Component, Default, InspectorOptions)]
 pub struct Move {
+    #[inspector(min = 0.0, max = 2000.0)]
     pub speed: f32,
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect, InspectorOptions, Default)]
+#[reflect(Component, Default, InspectorOptions)]
 pub struct RotateToPlayer {
+    #[inspector(min = 0.0, max = 720.0)]
     pub speed: f32,
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect, InspectorOptions, Default)]
+#[reflect(Component, Default, InspectorOptions)]
 pub struct Decay {
+    #[inspector(min = 0.0)]
     pub max_seconds: f32,
+    #[inspector(min = 0.0)]
     pub elapsed_time: f32,
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
 pub struct Bullet;
+
+/// Something enemies may choose to chase. A higher threat wins over a closer target.
+#[derive(Component, Reflect, InspectorOptions, Default)]
+#[reflect(Component, Default, InspectorOptions)]
+pub struct Targetable {
+    #[inspector(min = 0.0, max = 10.0)]
+    pub threat: f32,
+}
+
+/// The entity currently being chased, if any.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
+pub struct Target(pub Option<Entity>);
+
+/// What an entity with a `Target` does while it has nothing to chase.
+#[derive(Component, Reflect, InspectorOptions, Clone, Copy, Default)]
+#[reflect(Component, Default, InspectorOptions)]
+pub enum Idle {
+    Wander {
+        #[inspector(min = 0.0, max = 720.0)]
+        turn_speed: f32,
+    },
+    #[default]
+    Stand,
+}
+
+/// Distance travelled per second during the last frame.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
+pub struct Velocity(pub Vec2);
+
+#[derive(
+    Component, Reflect, Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Default,
+)]
+#[reflect(Component, Default)]
+pub enum EnemyArchetype {
+    #[default]
+    Grunt,
+    Hunter,
+    Swarmer,
+}
+
+impl EnemyArchetype {
+    pub const ALL: [EnemyArchetype; 3] = [
+        EnemyArchetype::Grunt,
+        EnemyArchetype::Hunter,
+        EnemyArchetype::Swarmer,
+    ];
+
+    /// Points awarded for killing this kind of enemy.
+    pub fn score(self) -> u32 {
+        match self {
+            EnemyArchetype::Grunt => 10,
+            EnemyArchetype::Hunter => 20,
+            EnemyArchetype::Swarmer => 5,
+        }
+    }
+}
+
+/// Per-entity steering state. `desired` is the blended direction, its length is the throttle.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
+pub struct Steering {
+    pub desired: Vec2,
+    pub wander_angle: f32,
+}
+
+#[derive(Reflect, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum ObstacleShape {
+    Rect { half_size: Vec2 },
+    Circle { radius: f32 },
+}
+
+impl Default for ObstacleShape {
+    fn default() -> Self {
+        Self::Circle { radius: 0.0 }
+    }
+}
+
+/// Static level geometry that blocks players, enemies and bullets.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
+pub struct Obstacle(pub ObstacleShape);
+
+/// Waypoints around obstacles towards the `Target`, empty while the target is in plain sight.
+#[derive(Component, Reflect, InspectorOptions, Default)]
+#[reflect(Component, Default, InspectorOptions)]
+pub struct PathFollow {
+    pub waypoints: Vec<Vec2>,
+    #[inspector(min = 0.0)]
+    pub repath_in: f32,
+}
+
+/// Marks where an enemy is about to appear. It materializes once `elapsed` reaches `duration`.
+#[derive(Component, Reflect, InspectorOptions, Default)]
+#[reflect(Component, Default, InspectorOptions)]
+pub struct SpawnTelegraph {
+    pub archetype: EnemyArchetype,
+    #[inspector(min = 0.05, max = 5.0)]
+    pub duration: f32,
+    #[inspector(min = 0.0)]
+    pub elapsed: f32,
+}
+
+/// What happens to a moving entity that leaves the arena.
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[reflect(Component, Default)]
+pub enum BoundaryBehavior {
+    #[default]
+    Clamp,
+    Wrap,
+    Bounce,
+    Despawn,
+}
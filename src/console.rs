@@ -0,0 +1,498 @@
+use std::collections::BTreeMap;
+
+use bevy::{input::InputSystem, prelude::*, window::ReceivedCharacter};
+
+use crate::{
+    bot::{Bot, BotStrategy},
+    components::*,
+    events::EnemyKilled,
+    prefabs,
+    profile::{Profile, WeaponKind},
+    resources::{Arena, GameRng, RoundConfig, RoundParams, RoundPhase, SpawnSettings},
+    states::AppState,
+    systems::{rounds, spawning},
+};
+
+/// Lines of output kept around, older ones are dropped.
+const MAX_LINES: usize = 200;
+/// Lines of output shown while the console is open.
+const VISIBLE_LINES: usize = 14;
+/// Highest round `round` jumps to.
+const MAX_ROUND: u32 = 100;
+/// Most enemies a round jumped to spawns, the wave curve outgrows any frame budget.
+const MAX_ROUND_ENEMIES: u32 = 5000;
+
+/// A submitted line, split into the command name and its arguments.
+#[derive(Event, Clone, Debug)]
+pub struct ConsoleCommand {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl ConsoleCommand {
+    /// Parses argument `i`, printing the usage to the console when it is missing or malformed.
+    pub fn arg<T: std::str::FromStr>(&self, i: usize, console: &mut Console) -> Option<T> {
+        let parsed = self.args.get(i).and_then(|a| a.parse().ok());
+        if parsed.is_none() {
+            console.print(format!(
+                "bad or missing argument {} for {}",
+                i + 1,
+                self.name
+            ));
+        }
+        parsed
+    }
+}
+
+/// Every command the console accepts, mapped to its usage line shown by `help`.
+#[derive(Resource, Default)]
+pub struct ConsoleCommands(BTreeMap<&'static str, &'static str>);
+
+/// Drop-down console toggled with the backtick key.
+#[derive(Resource, Default)]
+pub struct Console {
+    pub open: bool,
+    input: String,
+    lines: Vec<String>,
+    history: Vec<String>,
+    /// How far back in `history` the up arrow went.
+    recalled: usize,
+}
+
+impl Console {
+    pub fn print(&mut self, line: impl Into<String>) {
+        self.lines.push(line.into());
+        if self.lines.len() > MAX_LINES {
+            self.lines.remove(0);
+        }
+    }
+}
+
+/// Where command handlers run, after the console submitted this frame's line.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConsoleSet;
+
+pub trait ConsoleAppExt {
+    /// Registers `name` for `help` and the unknown command check and adds its handler. Handlers
+    /// read `ConsoleCommand` events and pick theirs with `calls`.
+    fn add_console_command<M>(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        handler: impl IntoSystemConfigs<M>,
+    ) -> &mut Self;
+}
+
+impl ConsoleAppExt for App {
+    fn add_console_command<M>(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        handler: impl IntoSystemConfigs<M>,
+    ) -> &mut Self {
+        self.init_resource::<ConsoleCommands>();
+        self.world
+            .resource_mut::<ConsoleCommands>()
+            .0
+            .insert(name, usage);
+        self.add_systems(Update, handler.in_set(ConsoleSet))
+    }
+}
+
+/// This frame's invocations of `name`.
+pub fn calls<'a>(
+    events: &'a mut EventReader<ConsoleCommand>,
+    name: &'a str,
+) -> impl Iterator<Item = &'a ConsoleCommand> + 'a {
+    events.iter().filter(move |c| c.name == name)
+}
+
+/// Edits and submits the input line. While the console is open the keys are swallowed so the
+/// game does not react to typing.
+fn console_input(
+    mut console: ResMut<Console>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut chars: EventReader<ReceivedCharacter>,
+    registry: Res<ConsoleCommands>,
+    mut submitted: EventWriter<ConsoleCommand>,
+) {
+    if keys.just_pressed(KeyCode::Grave) {
+        console.open = !console.open;
+        chars.clear();
+        keys.reset_all();
+        return;
+    }
+    if !console.open {
+        chars.clear();
+        return;
+    }
+    for c in chars.iter() {
+        if !c.char.is_control() && c.char != '`' {
+            console.input.push(c.char);
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+    if keys.just_pressed(KeyCode::Up) && console.recalled < console.history.len() {
+        console.recalled += 1;
+        console.input = console.history[console.history.len() - console.recalled].clone();
+    }
+    if keys.just_pressed(KeyCode::Down) && console.recalled > 0 {
+        console.recalled -= 1;
+        console.input = match console.recalled {
+            0 => String::new(),
+            n => console.history[console.history.len() - n].clone(),
+        };
+    }
+    if keys.just_pressed(KeyCode::Return) {
+        let line = std::mem::take(&mut console.input);
+        console.recalled = 0;
+        let mut words = line.split_whitespace().map(str::to_lowercase);
+        if let Some(name) = words.next() {
+            console.print(format!("> {line}"));
+            console.history.push(line.clone());
+            if name == "help" {
+                for usage in registry.0.values() {
+                    console.print(format!("  {usage}"));
+                }
+            } else if registry.0.contains_key(name.as_str()) {
+                submitted.send(ConsoleCommand {
+                    name,
+                    args: words.collect(),
+                });
+            } else {
+                console.print(format!("unknown command {name}, try help"));
+            }
+        }
+    }
+    keys.reset_all();
+}
+
+#[derive(Component)]
+struct ConsoleRoot;
+
+#[derive(Component)]
+struct ConsoleText;
+
+fn show_console(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    console: Res<Console>,
+    root: Query<Entity, With<ConsoleRoot>>,
+    mut text: Query<&mut Text, With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    if !console.open {
+        for e in &root {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    }
+    let start = console.lines.len().saturating_sub(VISIBLE_LINES);
+    let mut value = console.lines[start..].join("\n");
+    value.push_str(&format!("\n> {}_", console.input));
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = value;
+        return;
+    }
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(0.),
+                    width: Val::Percent(100.),
+                    height: Val::Percent(40.),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::FlexEnd,
+                    padding: UiRect::all(Val::Px(8.)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            ConsoleRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    value,
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 18.0,
+                        color: Color::rgb(0.8, 1.0, 0.8),
+                    },
+                ),
+                ConsoleText,
+            ));
+        });
+}
+
+fn spawn_command(
+    mut events: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    arena: Res<Arena>,
+    settings: Res<SpawnSettings>,
+    players: Query<&Transform, With<Player>>,
+) {
+    for call in calls(&mut events, "spawn") {
+        let name = call.args.first().map_or("", String::as_str);
+        let Some(archetype) = EnemyArchetype::ALL
+            .into_iter()
+            .find(|a| format!("{a:?}").eq_ignore_ascii_case(name))
+        else {
+            console.print(format!("unknown archetype {name:?}"));
+            continue;
+        };
+        let count = match call.args.len() > 1 {
+            true => call.arg(1, &mut console).unwrap_or(0),
+            false => 1,
+        };
+        let players: Vec<Vec2> = players.iter().map(|t| t.translation.truncate()).collect();
+        for _ in 0..count {
+            let pos = spawning::random_spawn_point(&mut rng.0, &arena, &settings, &players);
+            spawning::spawn_telegraph(&mut commands, &mut rng.0, pos, archetype, &settings);
+        }
+        console.print(format!("spawning {count} {archetype:?}"));
+    }
+}
+
+fn god_command(
+    mut events: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut commands: Commands,
+    player: Query<(Entity, Option<&God>), With<Player>>,
+) {
+    for _ in calls(&mut events, "god") {
+        let Ok((e, god)) = player.get_single() else {
+            console.print("no player");
+            continue;
+        };
+        match god {
+            Some(_) => {
+                commands.entity(e).remove::<God>();
+                console.print("god mode off");
+            }
+            None => {
+                commands.entity(e).insert(God);
+                console.print("god mode on");
+            }
+        }
+    }
+}
+
+fn heal_command(
+    mut events: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut player: Query<(&mut Health, &MaxHealth), With<Player>>,
+) {
+    for _ in calls(&mut events, "heal") {
+        for (mut health, max) in &mut player {
+            health.0 = max.0;
+        }
+        console.print("healed");
+    }
+}
+
+/// Jumps to the warm-up of round `n`, sized as if every round before it had been played.
+fn round_command(
+    mut events: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut commands: Commands,
+    round: Option<ResMut<RoundParams>>,
+    config: Res<RoundConfig>,
+    hostiles: Query<Entity, Or<(With<Enemy>, With<SpawnTelegraph>)>>,
+) {
+    let Some(mut round) = round else {
+        calls(&mut events, "round").for_each(|_| console.print("no run in progress"));
+        return;
+    };
+    for call in calls(&mut events, "round") {
+        let Some(n) = call.arg::<u32>(0, &mut console).filter(|n| *n > 0) else {
+            continue;
+        };
+        for e in &hostiles {
+            commands.entity(e).despawn_recursive();
+        }
+        round.round = 1;
+        round.enemies = config.first_enemies;
+        while round.round < n.min(MAX_ROUND) {
+            rounds::next_round(&mut round, &config);
+            round.enemies = round.enemies.min(MAX_ROUND_ENEMIES);
+        }
+        round.phase = RoundPhase::WarmUp;
+        round.countdown = rounds::WARM_UP_SECONDS;
+        console.print(format!("round {}, {} enemies", round.round, round.enemies));
+    }
+}
+
+fn timescale_command(
+    mut events: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut time: ResMut<Time>,
+) {
+    for call in calls(&mut events, "timescale") {
+        let Some(scale) = call
+            .arg::<f32>(0, &mut console)
+            .filter(|s| s.is_finite() && *s >= 0.0)
+        else {
+            continue;
+        };
+        time.set_relative_speed(scale);
+        console.print(format!("timescale {scale}"));
+    }
+}
+
+/// Kills every enemy as if shot, and drops the ones still telegraphed.
+fn kill_all_command(
+    mut events: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut commands: Commands,
+    enemies: Query<(Entity, &Transform, &EnemyArchetype), With<Enemy>>,
+    telegraphs: Query<Entity, With<SpawnTelegraph>>,
+    mut killed: EventWriter<EnemyKilled>,
+) {
+    for _ in calls(&mut events, "kill_all") {
+        for (e, t, archetype) in &enemies {
+            prefabs::release(&mut commands, e);
+            killed.send(EnemyKilled {
+                position: t.translation.truncate(),
+                archetype: *archetype,
+            });
+        }
+        for e in &telegraphs {
+            commands.entity(e).despawn_recursive();
+        }
+        console.print(format!("killed {}", enemies.iter().count()));
+    }
+}
+
+fn seed_command(
+    mut events: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut rng: ResMut<GameRng>,
+) {
+    for call in calls(&mut events, "seed") {
+        if let Some(seed) = call.arg::<u64>(0, &mut console) {
+            *rng = GameRng::from_seed(seed);
+            console.print(format!("seeded with {seed}"));
+        }
+    }
+}
+
+fn state_command(
+    mut events: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for call in calls(&mut events, "state") {
+        let state = match call.args.first().map(String::as_str) {
+            Some("menu") => AppState::Menu,
+            Some("ingame") => AppState::InGame,
+            Some("gameover") => AppState::GameOver,
+            Some("shop") => AppState::Shop,
+            Some("editor") => AppState::Editor,
+            other => {
+                console.print(format!("unknown state {other:?}"));
+                continue;
+            }
+        };
+        next_state.set(state);
+        console.print(format!("switching to {state:?}"));
+    }
+}
+
+/// Hands the player over to a bot, or takes it back.
+fn bot_command(
+    mut events: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut commands: Commands,
+) {
+    for call in calls(&mut events, "bot") {
+        match call.args.first().map(String::as_str) {
+            Some("off") => {
+                commands.remove_resource::<Bot>();
+                console.print("bot off");
+            }
+            Some(name) => match name.parse::<BotStrategy>() {
+                Ok(strategy) => {
+                    commands.insert_resource(Bot(strategy));
+                    console.print(format!("bot {}", strategy.name()));
+                }
+                Err(e) => console.print(e),
+            },
+            None => console.print("bot <kite|strafe|nearest|off>"),
+        }
+    }
+}
+
+/// Swaps the live player's weapon for this run only, the saved profile is left alone.
+fn give_command(
+    mut events: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    profile: Res<Profile>,
+    mut player: Query<(Entity, &mut PlayerStats), With<Player>>,
+    mut given: Local<Option<(Entity, WeaponKind)>>,
+) {
+    for call in calls(&mut events, "give") {
+        let name = call.args.first().map_or("", String::as_str);
+        let Some(weapon) = WeaponKind::ALL
+            .into_iter()
+            .find(|w| format!("{w:?}").eq_ignore_ascii_case(name))
+        else {
+            console.print(format!("unknown weapon {name:?}"));
+            continue;
+        };
+        for (e, mut stats) in &mut player {
+            // a player spawned since the last give still holds the loadout's weapon
+            let old = match *given {
+                Some((given_to, old)) if given_to == e => old,
+                _ => profile.loadout.weapon,
+            };
+            for m in old.modifiers() {
+                if let Some(i) = stats.modifiers.iter().position(|x| *x == m) {
+                    stats.modifiers.remove(i);
+                }
+            }
+            stats.modifiers.extend(weapon.modifiers());
+            *given = Some((e, weapon));
+        }
+        console.print(format!("equipped {weapon:?}"));
+    }
+}
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>()
+            .init_resource::<ConsoleCommands>()
+            .add_event::<ConsoleCommand>()
+            .add_systems(PreUpdate, console_input.after(InputSystem))
+            .add_systems(Update, show_console.after(ConsoleSet))
+            .add_console_command(
+                "spawn",
+                "spawn <grunt|hunter|swarmer> [count]",
+                spawn_command.run_if(in_state(AppState::InGame)),
+            )
+            .add_console_command("god", "god: toggle invulnerability", god_command)
+            .add_console_command("heal", "heal: restore full health", heal_command)
+            .add_console_command("round", "round <n>: jump to round n", round_command)
+            .add_console_command("timescale", "timescale <x>", timescale_command)
+            .add_console_command("kill_all", "kill_all: kill every enemy", kill_all_command)
+            .add_console_command("seed", "seed <n>: reseed the game rng", seed_command)
+            .add_console_command(
+                "state",
+                "state <menu|ingame|gameover|shop|editor>",
+                state_command,
+            )
+            .add_console_command("give", "give <pistol|spreadgun|railgun>", give_command)
+            .add_console_command("bot", "bot <kite|strafe|nearest|off>", bot_command);
+    }
+}
@@ -0,0 +1,130 @@
+use bevy::{input::common_conditions::input_just_pressed, math::Vec3Swizzles, prelude::*};
+
+use crate::{
+    components::*,
+    resources::Arena,
+    states::{AppState, GameplaySet},
+};
+
+const FORWARD_LENGTH: f32 = 16.0;
+
+/// Whether the gizmo overlay is drawn, toggled with F3.
+#[derive(Resource, Default)]
+pub struct DebugDraw {
+    pub enabled: bool,
+}
+
+/// Holds the gameplay schedule still while `enabled`, letting `pending` frames through.
+/// F7 toggles it, F8 advances one frame.
+#[derive(Resource, Default)]
+pub struct FrameStep {
+    pub enabled: bool,
+    pub pending: u32,
+}
+
+pub fn gameplay_running(step: Res<FrameStep>) -> bool {
+    !step.enabled || step.pending > 0
+}
+
+fn toggle_debug_draw(mut draw: ResMut<DebugDraw>) {
+    draw.enabled = !draw.enabled;
+}
+
+fn frame_step_input(keyboard_input: Res<Input<KeyCode>>, mut step: ResMut<FrameStep>) {
+    if keyboard_input.just_pressed(KeyCode::F7) {
+        step.enabled = !step.enabled;
+        step.pending = 0;
+        info!("frame step {}", if step.enabled { "on" } else { "off" });
+    }
+    if step.enabled && keyboard_input.just_pressed(KeyCode::F8) {
+        step.pending += 1;
+    }
+}
+
+/// Runs after the gameplay schedule so a requested step lasts exactly one frame.
+fn consume_step(mut step: ResMut<FrameStep>) {
+    step.pending = step.pending.saturating_sub(1);
+}
+
+fn draw_arena(mut gizmos: Gizmos, arena: Res<Arena>) {
+    gizmos.rect_2d(Vec2::ZERO, 0.0, arena.bounds, Color::GRAY);
+    for zone in &arena.spawn_zones {
+        gizmos.rect_2d(zone.center, 0.0, zone.half_size * 2.0, Color::ORANGE_RED);
+    }
+}
+
+/// Sensors colored by faction, forward vectors and the blended steering direction.
+fn draw_bodies(
+    mut gizmos: Gizmos,
+    query: Query<(
+        &Transform,
+        &Sensor,
+        Option<&Player>,
+        Option<&Enemy>,
+        Option<&Steering>,
+    )>,
+) {
+    for (t, sensor, player, enemy, steering) in &query {
+        let pos = t.translation.xy();
+        let color = match (player, enemy) {
+            (Some(_), _) => Color::GREEN,
+            (_, Some(_)) => Color::RED,
+            _ => Color::YELLOW,
+        };
+        gizmos.circle_2d(pos, sensor.radius, color);
+        if player.is_none() && enemy.is_none() {
+            continue;
+        }
+        gizmos.line_2d(pos, pos + t.up().xy() * FORWARD_LENGTH, Color::WHITE);
+        if let Some(steering) = steering {
+            gizmos.line_2d(pos, pos + steering.desired * FORWARD_LENGTH, Color::FUCHSIA);
+        }
+    }
+}
+
+/// Lines from every chaser to its target, and the waypoints it follows around obstacles.
+fn draw_targeting(
+    mut gizmos: Gizmos,
+    chasers: Query<(&Transform, &Target, Option<&PathFollow>)>,
+    targets: Query<&Transform, With<Targetable>>,
+) {
+    for (t, target, path) in &chasers {
+        let pos = t.translation.xy();
+        if let Some(path) = path.filter(|p| !p.waypoints.is_empty()) {
+            let points = std::iter::once(pos).chain(path.waypoints.iter().copied());
+            gizmos.linestrip_2d(points, Color::CYAN);
+        }
+        if let Some(target_t) = target.0.and_then(|e| targets.get(e).ok()) {
+            gizmos.line_2d(
+                pos,
+                target_t.translation.xy(),
+                Color::rgba(1.0, 0.4, 0.4, 0.4),
+            );
+        }
+    }
+}
+
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugDraw>()
+            .init_resource::<FrameStep>()
+            .configure_set(Update, GameplaySet.run_if(gameplay_running))
+            .add_systems(
+                Update,
+                (
+                    toggle_debug_draw.run_if(input_just_pressed(KeyCode::F3)),
+                    frame_step_input,
+                )
+                    .before(GameplaySet),
+            )
+            .add_systems(
+                Update,
+                (draw_arena, draw_bodies, draw_targeting).run_if(
+                    in_state(AppState::InGame).and_then(|draw: Res<DebugDraw>| draw.enabled),
+                ),
+            )
+            .add_systems(Last, consume_step);
+    }
+}
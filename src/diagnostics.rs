@@ -1,21 +1,238 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt::Write as _,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
 use bevy::{
+    app::AppExit,
+    core::FrameCount,
     diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    ecs::system::{CombinatorSystem, Combine},
+    input::common_conditions::input_just_pressed,
     prelude::*,
 };
 
 use crate::{
-    components::{Enemy, Health, Player},
+    components::{Bullet, Enemy},
+    particles::Particle,
+    resources::CollisionStats,
     states::AppState,
 };
 
-#[derive(Component)]
-struct TextChanges;
+/// Frames the percentiles are taken over.
+const WINDOW: usize = 600;
+/// Set to a file path to dump every frame's metrics there on exit.
+pub const METRICS_CSV_ENV: &str = "METRICS_CSV";
 
-#[derive(Component)]
-struct EnemyCounter;
+/// Filled by `timed` systems as they finish, drained by `MetricsPlugin`. Behind a lock so the timed
+/// systems only read the resource and can still run in parallel.
+#[derive(Resource, Default)]
+struct SystemTimes(Mutex<Vec<(&'static str, Duration)>>);
+
+struct Timed;
+
+impl<A, B> Combine<A, B> for Timed
+where
+    A: System<In = (), Out = ()>,
+    B: System<In = Duration, Out = ()>,
+{
+    type In = ();
+    type Out = ();
+
+    fn combine(_input: (), a: impl FnOnce(()), b: impl FnOnce(Duration)) -> Self::Out {
+        let start = Instant::now();
+        a(());
+        b(start.elapsed());
+    }
+}
+
+/// Wraps a system so its run time shows up in `Metrics` under `name`. Ordering against the
+/// wrapped system keeps working. Without `MetricsPlugin` nothing is recorded.
+pub fn timed<M>(
+    name: &'static str,
+    system: impl IntoSystem<(), (), M>,
+) -> impl System<In = (), Out = ()> {
+    CombinatorSystem::<Timed, _, _>::new(
+        IntoSystem::into_system(system),
+        IntoSystem::into_system(
+            move |In(elapsed): In<Duration>, times: Option<Res<SystemTimes>>| {
+                if let Some(times) = times {
+                    times.0.lock().unwrap().push((name, elapsed));
+                }
+            },
+        ),
+        Cow::Borrowed(name),
+    )
+}
+
+/// One frame as kept by `Metrics::record`. Times are in milliseconds.
+pub struct FrameMetrics {
+    pub frame: u32,
+    pub time: f64,
+    pub frame_ms: f64,
+    pub enemies: usize,
+    pub bullets: usize,
+    pub particles: usize,
+    pub collision_pairs: u32,
+    pub systems: Vec<(&'static str, f64)>,
+}
+
+/// Per-frame counts and timings, with a rolling window of frame times for the percentiles.
+#[derive(Resource, Default)]
+pub struct Metrics {
+    frame_times: VecDeque<f64>,
+    pub enemies: usize,
+    pub bullets: usize,
+    pub particles: usize,
+    pub collision_pairs: u32,
+    /// Smoothed milliseconds per timed system.
+    pub systems: BTreeMap<&'static str, f64>,
+    /// Keeps every frame for `write_csv` when set.
+    pub record: bool,
+    rows: Vec<FrameMetrics>,
+}
+
+impl Metrics {
+    /// Frame time in milliseconds at percentile `p` (0-100) of the window.
+    pub fn percentile(&self, p: f64) -> f64 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+        let mut sorted: Vec<f64> = self.frame_times.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        let i = ((sorted.len() - 1) as f64 * p / 100.0).round() as usize;
+        sorted[i]
+    }
+
+    pub fn frames(&self) -> &[FrameMetrics] {
+        &self.rows
+    }
+
+    pub fn clear_frames(&mut self) {
+        self.rows.clear();
+    }
+
+    pub fn write_csv(&self, path: &str) -> std::io::Result<()> {
+        let names: BTreeSet<&str> = self
+            .rows
+            .iter()
+            .flat_map(|r| r.systems.iter().map(|(name, _)| *name))
+            .collect();
+        let mut csv =
+            String::from("frame,time_s,frame_ms,enemies,bullets,particles,collision_pairs");
+        for name in &names {
+            write!(csv, ",{name}_ms").unwrap();
+        }
+        csv.push('\n');
+        for r in &self.rows {
+            write!(
+                csv,
+                "{},{:.4},{:.4},{},{},{},{}",
+                r.frame, r.time, r.frame_ms, r.enemies, r.bullets, r.particles, r.collision_pairs
+            )
+            .unwrap();
+            for name in &names {
+                let ms: f64 = r
+                    .systems
+                    .iter()
+                    .filter(|(n, _)| n == name)
+                    .map(|(_, ms)| ms)
+                    .sum();
+                write!(csv, ",{ms:.4}").unwrap();
+            }
+            csv.push('\n');
+        }
+        std::fs::write(path, csv)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn collect_metrics(
+    mut metrics: ResMut<Metrics>,
+    times: Res<SystemTimes>,
+    time: Res<Time>,
+    frame: Res<FrameCount>,
+    collisions: Res<CollisionStats>,
+    enemies: Query<(), With<Enemy>>,
+    bullets: Query<(), With<Bullet>>,
+    particles: Query<(), With<Particle>>,
+) {
+    let frame_ms = time.delta_seconds_f64() * 1e3;
+    if metrics.frame_times.len() == WINDOW {
+        metrics.frame_times.pop_front();
+    }
+    metrics.frame_times.push_back(frame_ms);
+    metrics.enemies = enemies.iter().len();
+    metrics.bullets = bullets.iter().len();
+    metrics.particles = particles.iter().len();
+    metrics.collision_pairs = collisions.take();
+
+    // a system run more than once a frame is summed
+    let mut systems: Vec<(&'static str, f64)> = Vec::new();
+    for (name, elapsed) in times.0.lock().unwrap().drain(..) {
+        let ms = elapsed.as_secs_f64() * 1e3;
+        match systems.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, total)) => *total += ms,
+            None => systems.push((name, ms)),
+        }
+    }
+    for (name, ms) in &systems {
+        let smoothed = metrics.systems.entry(*name).or_insert(*ms);
+        *smoothed = *smoothed * 0.9 + ms * 0.1;
+    }
+
+    if metrics.record {
+        let row = FrameMetrics {
+            frame: frame.0,
+            time: time.elapsed_seconds_f64(),
+            frame_ms,
+            enemies: metrics.enemies,
+            bullets: metrics.bullets,
+            particles: metrics.particles,
+            collision_pairs: metrics.collision_pairs,
+            systems,
+        };
+        metrics.rows.push(row);
+    }
+}
+
+fn dump_metrics(metrics: Res<Metrics>) {
+    let Ok(path) = std::env::var(METRICS_CSV_ENV) else {
+        return;
+    };
+    match metrics.write_csv(&path) {
+        Ok(()) => info!("wrote metrics to {path}"),
+        Err(e) => error!("failed to write {path}: {e}"),
+    }
+}
+
+/// Collects `Metrics` without drawing anything, so headless apps can use it.
+pub struct MetricsPlugin;
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Metrics {
+            record: std::env::var_os(METRICS_CSV_ENV).is_some(),
+            ..default()
+        })
+        .init_resource::<SystemTimes>()
+        .init_resource::<CollisionStats>()
+        .add_systems(
+            Last,
+            (collect_metrics, dump_metrics.run_if(on_event::<AppExit>())).chain(),
+        );
+    }
+}
+
+/// Whether the overlay lists the timed systems, toggled with F4.
+#[derive(Resource, Default)]
+struct ShowSystemTimes(bool);
 
 #[derive(Component)]
-struct PlayerHealth;
+struct TextChanges;
 
 fn infotext_system(mut commands: Commands, asset_server: Res<AssetServer>) {
     let font = asset_server.load("fonts/FiraSans-Bold.ttf");
@@ -30,7 +247,7 @@ fn infotext_system(mut commands: Commands, asset_server: Res<AssetServer>) {
         )])
         .with_style(Style {
             position_type: PositionType::Absolute,
-            top: Val::Px(5.0),
+            bottom: Val::Px(5.0),
             left: Val::Px(15.0),
             ..default()
         }),
@@ -38,71 +255,15 @@ fn infotext_system(mut commands: Commands, asset_server: Res<AssetServer>) {
     ));
 }
 
-fn enemy_counter(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let font = asset_server.load("fonts/Kenney Mini.ttf");
-    commands.spawn((
-        TextBundle::from_sections([
-            TextSection::new(
-                "Enemies: ",
-                TextStyle {
-                    font: font.clone(),
-                    font_size: 20.0,
-                    color: Color::WHITE,
-                },
-            ),
-            TextSection::new(
-                "",
-                TextStyle {
-                    font: font.clone(),
-                    font_size: 20.0,
-                    color: Color::WHITE,
-                },
-            ),
-        ])
-        .with_style(Style {
-            position_type: PositionType::Absolute,
-            top: Val::Px(5.0),
-            right: Val::Px(15.0),
-            ..default()
-        }),
-        EnemyCounter,
-    ));
-}
-
-fn player_health(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let font = asset_server.load("fonts/Kenney Mini.ttf");
-    commands.spawn((
-        TextBundle::from_sections([
-            TextSection::new(
-                "Health: ",
-                TextStyle {
-                    font: font.clone(),
-                    font_size: 20.0,
-                    color: Color::WHITE,
-                },
-            ),
-            TextSection::new(
-                "",
-                TextStyle {
-                    font: font.clone(),
-                    font_size: 20.0,
-                    color: Color::WHITE,
-                },
-            ),
-        ])
-        .with_style(Style {
-            position_type: PositionType::Absolute,
-            top: Val::Px(35.0),
-            right: Val::Px(15.0),
-            ..default()
-        }),
-        PlayerHealth,
-    ));
+fn toggle_system_times(mut show: ResMut<ShowSystemTimes>) {
+    show.0 = !show.0;
 }
 
 fn change_text_system(
     time: Res<Time>,
     diagnostics: Res<DiagnosticsStore>,
+    metrics: Res<Metrics>,
+    show: Res<ShowSystemTimes>,
     mut query: Query<&mut Text, With<TextChanges>>,
 ) {
     for mut text in &mut query {
@@ -120,29 +281,35 @@ fn change_text_system(
                 frame_time = frame_time_smoothed;
             }
         }
-        text.sections[0].value = format!("FPS: {fps:.1} | {frame_time:.3} ms/frame",);
-    }
-}
-
-fn change_enemy_counter(
-    mut query: Query<&mut Text, With<EnemyCounter>>,
-    q_enemies: Query<Entity, With<Enemy>>,
-) {
-    for mut text in &mut query {
-        let total_enemies = q_enemies.iter().count();
-        text.sections[1].value = format!("{total_enemies}",);
+        let mut value = String::new();
+        if show.0 {
+            let mut systems: Vec<_> = metrics.systems.iter().collect();
+            systems.sort_by(|a, b| b.1.total_cmp(a.1));
+            for (name, ms) in systems {
+                writeln!(value, "{name}: {ms:.3} ms").unwrap();
+            }
+        }
+        writeln!(
+            value,
+            "enemies {} | bullets {} | particles {} | collision pairs {}",
+            metrics.enemies, metrics.bullets, metrics.particles, metrics.collision_pairs
+        )
+        .unwrap();
+        write!(
+            value,
+            "FPS: {fps:.1} | {frame_time:.3} ms/frame | p50 {:.2} p95 {:.2} p99 {:.2} ms",
+            metrics.percentile(50.0),
+            metrics.percentile(95.0),
+            metrics.percentile(99.0),
+        )
+        .unwrap();
+        text.sections[0].value = value;
     }
 }
 
-fn player_health_update(
-    mut query: Query<&mut Text, With<PlayerHealth>>,
-    p_health: Query<&Health, With<Player>>,
-) {
-    if let Ok(health) = p_health.get_single() {
-        for mut text in &mut query {
-            let v = health.0;
-            text.sections[1].value = format!("{v}",);
-        }
+fn cleanup_infotext(mut commands: Commands, query: Query<Entity, With<TextChanges>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
     }
 }
 
@@ -150,16 +317,18 @@ pub struct DiagnosticsPlugin;
 
 impl Plugin for DiagnosticsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(FrameTimeDiagnosticsPlugin)
-            .add_systems(Startup, (infotext_system, enemy_counter, player_health))
+        app.add_plugins((FrameTimeDiagnosticsPlugin, MetricsPlugin))
+            .init_resource::<ShowSystemTimes>()
+            .add_systems(OnEnter(AppState::InGame), infotext_system)
             .add_systems(
                 Update,
                 (
+                    toggle_system_times.run_if(input_just_pressed(KeyCode::F4)),
                     change_text_system,
-                    change_enemy_counter,
-                    player_health_update,
                 )
+                    .chain()
                     .run_if(in_state(AppState::InGame)),
-            );
+            )
+            .add_systems(OnExit(AppState::InGame), cleanup_infotext);
     }
 }
@@ -0,0 +1,228 @@
+use bevy::{input::mouse::MouseWheel, prelude::*};
+
+use crate::{
+    components::ObstacleShape,
+    level::{CurrentLevel, Level, ObstacleDef},
+    resources::{MouseWorldPos, SpawnZone},
+    states::AppState,
+};
+
+/// Placed objects snap to multiples of this many world units.
+const SNAP: f32 = 8.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Tool {
+    Wall,
+    Pillar,
+    SpawnZone,
+    PlayerSpawn,
+}
+
+#[derive(Resource)]
+struct EditorData {
+    help_entity: Entity,
+    tool: Tool,
+    vertical: bool,
+    size: f32,
+}
+
+fn setup_editor(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let help_entity = commands
+        .spawn(
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font,
+                    font_size: 18.0,
+                    color: Color::WHITE,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                left: Val::Px(15.0),
+                ..default()
+            }),
+        )
+        .id();
+    commands.insert_resource(EditorData {
+        help_entity,
+        tool: Tool::Wall,
+        vertical: false,
+        size: 40.0,
+    });
+}
+
+fn snap(pos: Vec2) -> Vec2 {
+    (pos / SNAP).round() * SNAP
+}
+
+fn wall_half_size(data: &EditorData) -> Vec2 {
+    match data.vertical {
+        true => Vec2::new(SNAP, data.size),
+        false => Vec2::new(data.size, SNAP),
+    }
+}
+
+fn tool_input(
+    mut data: ResMut<EditorData>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut wheel: EventReader<MouseWheel>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for (key, tool) in [
+        (KeyCode::Key1, Tool::Wall),
+        (KeyCode::Key2, Tool::Pillar),
+        (KeyCode::Key3, Tool::SpawnZone),
+        (KeyCode::Key4, Tool::PlayerSpawn),
+    ] {
+        if keyboard_input.just_pressed(key) {
+            data.tool = tool;
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::R) {
+        data.vertical = !data.vertical;
+    }
+    for ev in wheel.iter() {
+        data.size = (data.size + ev.y.signum() * SNAP).clamp(SNAP, 400.0);
+    }
+    if keyboard_input.just_pressed(KeyCode::Q) {
+        next_state.set(AppState::Menu);
+    }
+}
+
+/// Left click places the selected object, right click removes whatever is under the cursor.
+fn edit_level(
+    data: Res<EditorData>,
+    ms_input: Res<Input<MouseButton>>,
+    ms_pos: Res<MouseWorldPos>,
+    current: Res<CurrentLevel>,
+    mut levels: ResMut<Assets<Level>>,
+) {
+    let place = ms_input.just_pressed(MouseButton::Left);
+    let remove = ms_input.just_pressed(MouseButton::Right);
+    if !place && !remove {
+        return;
+    }
+    let Some(level) = levels.get_mut(&current.handle) else {
+        return;
+    };
+    let pos = snap(ms_pos.0);
+
+    if remove {
+        let hit = |center: Vec2, half_size: Vec2| (ms_pos.0 - center).abs().cmple(half_size).all();
+        let before = level.obstacles.len() + level.spawn_zones.len();
+        level.obstacles.retain(|o| match o.shape {
+            ObstacleShape::Rect { half_size } => !hit(o.position, half_size),
+            ObstacleShape::Circle { radius } => o.position.distance(ms_pos.0) > radius,
+        });
+        if level.obstacles.len() + level.spawn_zones.len() == before {
+            level.spawn_zones.retain(|z| !hit(z.center, z.half_size));
+        }
+        return;
+    }
+
+    match data.tool {
+        Tool::Wall => level.obstacles.push(ObstacleDef {
+            position: pos,
+            shape: ObstacleShape::Rect {
+                half_size: wall_half_size(&data),
+            },
+        }),
+        Tool::Pillar => level.obstacles.push(ObstacleDef {
+            position: pos,
+            shape: ObstacleShape::Circle {
+                radius: data.size / 2.0,
+            },
+        }),
+        Tool::SpawnZone => level.spawn_zones.push(SpawnZone {
+            center: pos,
+            half_size: Vec2::splat(data.size),
+        }),
+        Tool::PlayerSpawn => level.player_spawn = pos,
+    }
+}
+
+fn save_level(
+    keyboard_input: Res<Input<KeyCode>>,
+    current: Res<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+) {
+    let ctrl = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !(ctrl && keyboard_input.just_pressed(KeyCode::S)) {
+        return;
+    }
+    let Some(level) = levels.get(&current.handle) else {
+        return;
+    };
+    let path = format!("assets/{}", current.path);
+    let saved = ron::ser::to_string_pretty(level, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())
+        .and_then(|s| std::fs::write(&path, s).map_err(|e| e.to_string()));
+    match saved {
+        Ok(()) => info!("level saved to {path}"),
+        Err(e) => error!("failed to save level to {path}: {e}"),
+    }
+}
+
+fn draw_editor(
+    mut gizmos: Gizmos,
+    data: Res<EditorData>,
+    ms_pos: Res<MouseWorldPos>,
+    current: Res<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+) {
+    let Some(level) = levels.get(&current.handle) else {
+        return;
+    };
+    gizmos.rect_2d(Vec2::ZERO, 0.0, level.bounds, Color::GRAY);
+    for zone in &level.spawn_zones {
+        gizmos.rect_2d(zone.center, 0.0, zone.half_size * 2.0, Color::RED);
+    }
+    gizmos.circle_2d(level.player_spawn, 8.0, Color::GREEN);
+
+    let pos = snap(ms_pos.0);
+    let preview = Color::rgba(1.0, 1.0, 1.0, 0.5);
+    match data.tool {
+        Tool::Wall => gizmos.rect_2d(pos, 0.0, wall_half_size(&data) * 2.0, preview),
+        Tool::Pillar => {
+            gizmos.circle_2d(pos, data.size / 2.0, preview);
+        }
+        Tool::SpawnZone => gizmos.rect_2d(pos, 0.0, Vec2::splat(data.size * 2.0), preview),
+        Tool::PlayerSpawn => {
+            gizmos.circle_2d(pos, 8.0, preview);
+        }
+    }
+}
+
+fn update_help(data: Res<EditorData>, mut query: Query<&mut Text>) {
+    if !data.is_changed() {
+        return;
+    }
+    if let Ok(mut text) = query.get_mut(data.help_entity) {
+        text.sections[0].value = format!(
+            "[1] wall [2] pillar [3] spawn zone [4] player spawn | tool: {:?} size: {} | \
+             [R] rotate [wheel] resize [LMB] place [RMB] remove [Ctrl+S] save [Q] menu",
+            data.tool, data.size
+        );
+    }
+}
+
+fn cleanup_editor(mut commands: Commands, editor_data: Res<EditorData>) {
+    commands.entity(editor_data.help_entity).despawn_recursive();
+}
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Editor), setup_editor)
+            .add_systems(
+                Update,
+                (tool_input, edit_level, save_level, draw_editor, update_help)
+                    .run_if(in_state(AppState::Editor)),
+            )
+            .add_systems(OnExit(AppState::Editor), cleanup_editor);
+    }
+}
@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+
+use crate::components::EnemyArchetype;
+
+/// The player lost health to an enemy.
+#[derive(Event)]
+pub struct PlayerHit {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub damage: f32,
+}
+
+/// An enemy was destroyed by a bullet.
+#[derive(Event)]
+pub struct EnemyKilled {
+    pub position: Vec2,
+    pub archetype: EnemyArchetype,
+}
+
+/// The player fired a bullet from `position` heading along `direction`.
+#[derive(Event)]
+pub struct ShotFired {
+    pub position: Vec2,
+    pub direction: Vec2,
+}
+
+/// A bullet hit level geometry.
+#[derive(Event)]
+pub struct BulletImpact {
+    pub position: Vec2,
+}
+
+/// The player's health reached zero.
+#[derive(Event)]
+pub struct PlayerDied {
+    pub position: Vec2,
+}
+
+/// A new wave of enemies started spawning.
+#[derive(Event)]
+pub struct RoundStarted {
+    pub round: u32,
+}
+
+/// A round ended. `cleared` is set when every enemy was killed before time ran out.
+#[derive(Event)]
+pub struct RoundEnded {
+    pub round: u32,
+    pub cleared: bool,
+    pub bonus: u32,
+}
+
+/// The pointer moved onto a UI button.
+#[derive(Event)]
+pub struct ButtonHovered;
+
+/// A UI button was pressed.
+#[derive(Event)]
+pub struct ButtonPressed;
+
+pub struct GameEventsPlugin;
+
+impl Plugin for GameEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayerHit>()
+            .add_event::<EnemyKilled>()
+            .add_event::<ShotFired>()
+            .add_event::<BulletImpact>()
+            .add_event::<PlayerDied>()
+            .add_event::<RoundStarted>()
+            .add_event::<RoundEnded>()
+            .add_event::<ButtonHovered>()
+            .add_event::<ButtonPressed>();
+    }
+}
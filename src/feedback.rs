@@ -0,0 +1,238 @@
+use bevy::{prelude::*, sprite::Mesh2dHandle};
+
+use crate::{
+    components::{Dead, Decay, HitCooldown},
+    events::{EnemyKilled, PlayerHit},
+    prefabs::PoolAppExt,
+    states::{AppState, GameplaySet},
+};
+
+const FLASH_SECONDS: f32 = 0.1;
+/// Size of the flash outline relative to the flashing mesh.
+const OUTLINE_SCALE: f32 = 1.5;
+/// Visibility toggles per second while a hit cooldown runs.
+const BLINK_RATE: f32 = 12.0;
+
+/// Material every damaged entity is painted with while it flashes.
+#[derive(Resource)]
+pub struct FlashMaterial(pub Handle<ColorMaterial>);
+
+/// An entity painted with the flash material. `originals` restores it and its children, the
+/// `outline` behind it keeps the flash visible on white meshes.
+#[derive(Component)]
+pub struct HitFlash {
+    pub remains: f32,
+    pub originals: Vec<(Entity, Handle<ColorMaterial>)>,
+    pub outline: Option<Entity>,
+}
+
+/// World space text that drifts and fades until its `Decay` removes it.
+#[derive(Component)]
+pub struct FloatingText {
+    pub velocity: Vec2,
+}
+
+fn store_flash_material(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    let handle = materials.add(Color::WHITE.into());
+    commands.insert_resource(FlashMaterial(handle));
+}
+
+/// Paints `entity` and its children with the flash material and outlines it, or restarts a
+/// running flash.
+pub fn flash(
+    commands: &mut Commands,
+    entity: Entity,
+    flash_mat: &FlashMaterial,
+    flashing: &mut Query<&mut HitFlash>,
+    children: &Query<&Children>,
+    mats: &mut Query<&mut Handle<ColorMaterial>>,
+    meshes: &Query<&Mesh2dHandle>,
+) {
+    if let Ok(mut f) = flashing.get_mut(entity) {
+        f.remains = FLASH_SECONDS;
+        return;
+    }
+    let mut originals = Vec::new();
+    let nested = children.iter_descendants(entity);
+    for e in std::iter::once(entity).chain(nested) {
+        if let Ok(mut mat) = mats.get_mut(e) {
+            originals.push((e, mat.clone()));
+            *mat = flash_mat.0.clone();
+        }
+    }
+    let Some(mut entity) = commands.get_entity(entity) else {
+        return;
+    };
+    let outline = meshes.get(entity.id()).ok().map(|mesh| {
+        entity
+            .commands()
+            .spawn(ColorMesh2dBundle {
+                mesh: mesh.clone(),
+                material: flash_mat.0.clone(),
+                transform: Transform::from_xyz(0.0, 0.0, -0.5)
+                    .with_scale(Vec3::splat(OUTLINE_SCALE)),
+                ..default()
+            })
+            .id()
+    });
+    if let Some(outline) = outline {
+        entity.add_child(outline);
+    }
+    entity.insert(HitFlash {
+        remains: FLASH_SECONDS,
+        originals,
+        outline,
+    });
+}
+
+pub fn spawn_floating_text(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    position: Vec2,
+    value: String,
+    color: Color,
+) {
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                value,
+                TextStyle {
+                    font: asset_server.load("fonts/Kenney Mini.ttf"),
+                    font_size: 14.0,
+                    color,
+                },
+            ),
+            transform: Transform::from_translation(position.extend(2.0)),
+            ..default()
+        },
+        FloatingText {
+            velocity: Vec2::new(0.0, 30.0),
+        },
+        Decay {
+            max_seconds: 0.8,
+            elapsed_time: 0.0,
+        },
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn damage_feedback(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    flash_mat: Res<FlashMaterial>,
+    mut hits: EventReader<PlayerHit>,
+    mut kills: EventReader<EnemyKilled>,
+    mut flashing: Query<&mut HitFlash>,
+    children: Query<&Children>,
+    mut mats: Query<&mut Handle<ColorMaterial>>,
+    meshes: Query<&Mesh2dHandle>,
+) {
+    for hit in hits.iter() {
+        flash(
+            &mut commands,
+            hit.entity,
+            &flash_mat,
+            &mut flashing,
+            &children,
+            &mut mats,
+            &meshes,
+        );
+        spawn_floating_text(
+            &mut commands,
+            &asset_server,
+            hit.position + Vec2::Y * 8.0,
+            format!("-{}", hit.damage),
+            Color::rgb(1.0, 0.3, 0.3),
+        );
+    }
+    for kill in kills.iter() {
+        spawn_floating_text(
+            &mut commands,
+            &asset_server,
+            kill.position,
+            format!("+{}", kill.archetype.score()),
+            Color::rgb(1.0, 0.9, 0.4),
+        );
+    }
+}
+
+fn update_flash(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut HitFlash)>,
+    mut mats: Query<&mut Handle<ColorMaterial>>,
+    time: Res<Time>,
+) {
+    for (e, mut f) in &mut query {
+        f.remains -= time.delta_seconds();
+        if f.remains > 0.0 {
+            continue;
+        }
+        for (target, original) in f.originals.drain(..) {
+            if let Ok(mut mat) = mats.get_mut(target) {
+                *mat = original;
+            }
+        }
+        if let Some(outline) = f.outline.and_then(|o| commands.get_entity(o)) {
+            outline.despawn_recursive();
+        }
+        commands.entity(e).remove::<HitFlash>();
+    }
+}
+
+/// Blinks anything that is still invulnerable after a hit.
+fn blink_invulnerable(mut query: Query<(&HitCooldown, &mut Visibility, Option<&Dead>)>) {
+    for (cd, mut visibility, dead) in &mut query {
+        let hidden = dead.is_none()
+            && cd.time_remains > 0.0
+            && (cd.time_remains * BLINK_RATE) as u32 % 2 == 1;
+        let wanted = match hidden {
+            true => Visibility::Hidden,
+            false => Visibility::Inherited,
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
+
+fn update_floating_text(
+    mut query: Query<(&FloatingText, &Decay, &mut Transform, &mut Text)>,
+    time: Res<Time>,
+) {
+    for (floating, decay, mut t, mut text) in &mut query {
+        t.translation += (floating.velocity * time.delta_seconds()).extend(0.0);
+        let alpha = 1.0 - (decay.elapsed_time / decay.max_seconds).min(1.0);
+        for section in &mut text.sections {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
+
+fn cleanup_floating_text(mut commands: Commands, query: Query<Entity, With<FloatingText>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+pub struct FeedbackPlugin;
+
+impl Plugin for FeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.on_release(|entity| {
+            entity.remove::<HitFlash>();
+        })
+        .add_systems(Startup, store_flash_material)
+        .add_systems(
+            Update,
+            (
+                damage_feedback,
+                update_flash,
+                blink_invulnerable,
+                update_floating_text,
+            )
+                .run_if(in_state(AppState::InGame))
+                .in_set(GameplaySet),
+        )
+        .add_systems(OnExit(AppState::InGame), cleanup_floating_text);
+    }
+}
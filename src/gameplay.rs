@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::Decay,
+    diagnostics::timed,
+    events::EnemyKilled,
+    prefabs,
+    resources::{
+        ArchetypeConfig, CollisionStats, GameRng, PlayerInput, RoundConfig, Score, SpawnSettings,
+        SteeringConfig,
+    },
+    states::{AppState, GameplaySet},
+    systems::{collision, movement, player, rounds, spawning, steering, targeting},
+};
+
+fn decay_system(mut commands: Commands, mut query: Query<(Entity, &mut Decay)>, time: Res<Time>) {
+    for (e, mut d) in &mut query {
+        match d.elapsed_time > d.max_seconds {
+            true => prefabs::release(&mut commands, e),
+            false => d.elapsed_time += time.delta_seconds(),
+        }
+    }
+}
+
+fn score_system(mut kills: EventReader<EnemyKilled>, mut score: ResMut<Score>) {
+    for kill in kills.iter() {
+        score.0 += kill.archetype.score();
+    }
+}
+
+/// The simulation of a run, shared by the game and the benches. Reading the player's input and
+/// deciding when the run is over is left to the app.
+pub struct GameplayPlugin;
+
+impl Plugin for GameplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SteeringConfig>()
+            .init_resource::<SpawnSettings>()
+            .init_resource::<Score>()
+            .init_resource::<GameRng>()
+            .init_resource::<RoundConfig>()
+            .init_resource::<ArchetypeConfig>()
+            .init_resource::<CollisionStats>()
+            .init_resource::<PlayerInput>()
+            .add_systems(
+                Update,
+                (
+                    timed("decay", decay_system),
+                    timed("round", rounds::round_system),
+                    timed("score", score_system),
+                    timed("telegraph", spawning::telegraph_system),
+                    (
+                        timed("acquire_target", targeting::acquire_target_system),
+                        timed("steering", steering::steering_system),
+                        timed("rotate_to_player", movement::rotate_to_player_system),
+                    )
+                        .chain(),
+                    timed("bullet_enemy", collision::bullet_enemy),
+                    timed("player_enemy", collision::player_enemy),
+                    timed("bullet_obstacle", collision::bullet_obstacle),
+                    timed("obstacle_blocking", collision::obstacle_blocking)
+                        .after(movement::move_system)
+                        .after(player::movement_system),
+                    timed("boundary", movement::boundary_system)
+                        .after(collision::obstacle_blocking),
+                    timed("player_movement", player::movement_system),
+                    timed("aim", player::aim_system),
+                    timed("fire", player::fire_system),
+                    timed("died", player::died_system),
+                    timed("apply_stats", player::apply_stats_system),
+                )
+                    .run_if(in_state(AppState::InGame))
+                    .in_set(GameplaySet),
+            )
+            .add_systems(
+                Update,
+                timed("move", movement::move_system).in_set(GameplaySet),
+            );
+    }
+}
@@ -0,0 +1,332 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{Enemy, Health, HitCooldown, MaxHealth, Player, Weapon},
+    resources::{RoundParams, RoundPhase, Score},
+    states::AppState,
+    systems::rounds,
+};
+
+/// Window height the HUD font sizes are authored for.
+const REFERENCE_HEIGHT: f32 = 720.0;
+
+const SEGMENT_FULL: Color = Color::rgb(0.85, 0.2, 0.25);
+const SEGMENT_EMPTY: Color = Color::rgba(0.3, 0.3, 0.3, 0.6);
+
+#[derive(Component)]
+struct HudRoot;
+
+/// Text that keeps `size` relative to `REFERENCE_HEIGHT` as the window is resized.
+#[derive(Component)]
+struct HudText {
+    size: f32,
+}
+
+/// Big centred text announcing a round and its outcome.
+#[derive(Component)]
+struct RoundBanner;
+
+#[derive(Component)]
+struct HealthBar;
+
+#[derive(Component)]
+struct HealthSegment;
+
+#[derive(Component, Clone, Copy)]
+enum HudLabel {
+    Round,
+    Countdown,
+    Score,
+    Enemies,
+}
+
+#[derive(Component, Clone, Copy)]
+enum CooldownBar {
+    Weapon,
+    Invulnerable,
+}
+
+fn hud_text(font: &Handle<Font>, size: f32, color: Color) -> (TextBundle, HudText) {
+    let style = TextStyle {
+        font: font.clone(),
+        font_size: size,
+        color,
+    };
+    (TextBundle::from_section("", style), HudText { size })
+}
+
+fn spawn_cooldown_bar(
+    parent: &mut ChildBuilder,
+    font: &Handle<Font>,
+    label: &str,
+    bar: CooldownBar,
+) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                column_gap: Val::Vh(1.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            let (mut text, scale) = hud_text(font, 14.0, Color::WHITE);
+            text.text.sections[0].value = label.to_string();
+            parent.spawn((text, scale));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Vh(12.0),
+                        height: Val::Vh(1.0),
+                        ..default()
+                    },
+                    background_color: SEGMENT_EMPTY.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(100.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: Color::rgb(0.9, 0.8, 0.3).into(),
+                            ..default()
+                        },
+                        bar,
+                    ));
+                });
+        });
+}
+
+fn setup_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("fonts/Kenney Mini.ttf");
+    let corner = |align: AlignItems| Style {
+        flex_direction: FlexDirection::Column,
+        align_items: align,
+        row_gap: Val::Vh(1.0),
+        ..default()
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    padding: UiRect::all(Val::Vh(2.0)),
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::FlexStart,
+                    ..default()
+                },
+                ..default()
+            },
+            HudRoot,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Percent(100.0),
+                        top: Val::Percent(30.0),
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((hud_text(&font, 64.0, Color::WHITE), RoundBanner));
+                });
+            // health and cooldowns
+            parent
+                .spawn(NodeBundle {
+                    style: corner(AlignItems::FlexStart),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                column_gap: Val::Vh(0.5),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        HealthBar,
+                    ));
+                    spawn_cooldown_bar(parent, &font, "GUN", CooldownBar::Weapon);
+                    spawn_cooldown_bar(parent, &font, "SHIELD", CooldownBar::Invulnerable);
+                });
+            // round and countdown
+            parent
+                .spawn(NodeBundle {
+                    style: corner(AlignItems::Center),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((hud_text(&font, 28.0, Color::WHITE), HudLabel::Round));
+                    parent.spawn((hud_text(&font, 16.0, Color::GRAY), HudLabel::Countdown));
+                });
+            // score and enemies
+            parent
+                .spawn(NodeBundle {
+                    style: corner(AlignItems::FlexEnd),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((hud_text(&font, 28.0, Color::WHITE), HudLabel::Score));
+                    parent.spawn((hud_text(&font, 16.0, Color::GRAY), HudLabel::Enemies));
+                });
+        });
+}
+
+/// Keeps one segment per point of max health and fills as many as the player has left.
+fn update_health_bar(
+    mut commands: Commands,
+    player: Query<(&Health, &MaxHealth), With<Player>>,
+    bar: Query<(Entity, Option<&Children>), With<HealthBar>>,
+    mut segments: Query<&mut BackgroundColor, With<HealthSegment>>,
+) {
+    let Ok((health, max)) = player.get_single() else {
+        return;
+    };
+    let Ok((bar, children)) = bar.get_single() else {
+        return;
+    };
+    let wanted = max.0.ceil().max(0.0) as usize;
+    let children: Vec<Entity> = children.map(|c| c.to_vec()).unwrap_or_default();
+    if children.len() != wanted {
+        commands.entity(bar).despawn_descendants();
+        commands.entity(bar).with_children(|parent| {
+            for _ in 0..wanted {
+                parent.spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Vh(3.0),
+                            height: Val::Vh(2.0),
+                            ..default()
+                        },
+                        background_color: SEGMENT_EMPTY.into(),
+                        ..default()
+                    },
+                    HealthSegment,
+                ));
+            }
+        });
+        return;
+    }
+    for (i, e) in children.iter().enumerate() {
+        if let Ok(mut color) = segments.get_mut(*e) {
+            let full = (i as f32) < health.0;
+            *color = match full {
+                true => SEGMENT_FULL,
+                false => SEGMENT_EMPTY,
+            }
+            .into();
+        }
+    }
+}
+
+fn update_labels(
+    mut query: Query<(&mut Text, &HudLabel)>,
+    round: Res<RoundParams>,
+    score: Res<Score>,
+    enemies: Query<(), With<Enemy>>,
+) {
+    for (mut text, label) in &mut query {
+        text.sections[0].value = match label {
+            HudLabel::Round => format!("ROUND {}", round.round),
+            HudLabel::Countdown => match round.phase {
+                RoundPhase::WarmUp => "get ready".to_string(),
+                RoundPhase::Active => format!("time left {:.1}s", round.countdown.max(0.0)),
+                RoundPhase::Cleared => "round over".to_string(),
+                RoundPhase::Intermission => "intermission".to_string(),
+            },
+            HudLabel::Score => format!("{}", score.0),
+            HudLabel::Enemies => format!("enemies {}", enemies.iter().count()),
+        };
+    }
+}
+
+/// Shows the round number during warm-up and the outcome once it ends, fading out at the end.
+fn update_banner(round: Res<RoundParams>, mut query: Query<&mut Text, With<RoundBanner>>) {
+    let (value, duration) = match round.phase {
+        RoundPhase::WarmUp => (format!("ROUND {}", round.round), rounds::WARM_UP_SECONDS),
+        RoundPhase::Cleared if round.cleared => (
+            format!("ROUND {} CLEAR +{}", round.round, round.bonus),
+            rounds::CLEARED_SECONDS,
+        ),
+        RoundPhase::Cleared => (
+            format!("ROUND {} SURVIVED", round.round),
+            rounds::CLEARED_SECONDS,
+        ),
+        RoundPhase::Active | RoundPhase::Intermission => (String::new(), 1.0),
+    };
+    // fully visible for most of the phase, fading over the last half second
+    let alpha = (round.countdown / duration.min(0.5)).clamp(0.0, 1.0);
+    for mut text in &mut query {
+        text.sections[0].value = value.clone();
+        text.sections[0].style.color.set_a(alpha);
+    }
+}
+
+fn update_cooldowns(
+    player: Query<(&Weapon, &HitCooldown), With<Player>>,
+    mut bars: Query<(&mut Style, &CooldownBar)>,
+) {
+    let Ok((weapon, hit)) = player.get_single() else {
+        return;
+    };
+    for (mut style, bar) in &mut bars {
+        // the gun refills towards the next shot, the shield drains while it lasts
+        let fill = match bar {
+            CooldownBar::Weapon => 1.0 - weapon.remains / weapon.cooldown.max(f32::EPSILON),
+            CooldownBar::Invulnerable => hit.time_remains.max(0.0) / hit.time_full,
+        };
+        style.width = Val::Percent(fill.clamp(0.0, 1.0) * 100.0);
+    }
+}
+
+fn scale_text(windows: Query<&Window>, mut query: Query<(&mut Text, &HudText)>) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let scale = window.height() / REFERENCE_HEIGHT;
+    for (mut text, hud) in &mut query {
+        let size = hud.size * scale;
+        if text.sections[0].style.font_size != size {
+            for section in &mut text.sections {
+                section.style.font_size = size;
+            }
+        }
+    }
+}
+
+fn cleanup_hud(mut commands: Commands, query: Query<Entity, With<HudRoot>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InGame), setup_hud)
+            .add_systems(
+                Update,
+                (
+                    update_health_bar,
+                    update_labels,
+                    update_banner,
+                    update_cooldowns,
+                    scale_text,
+                )
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), cleanup_hud);
+    }
+}
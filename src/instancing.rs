@@ -0,0 +1,342 @@
+use bevy::{
+    asset::load_internal_asset,
+    core_pipeline::core_2d::Transparent2d,
+    ecs::{
+        query::ROQueryItem,
+        system::{lifetimeless::*, SystemParamItem},
+    },
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        mesh::{GpuBufferInfo, MeshVertexBufferLayout},
+        render_asset::RenderAssets,
+        render_phase::{
+            AddRenderCommand, DrawFunctions, PhaseItem, RenderCommand, RenderCommandResult,
+            RenderPhase, SetItemPipeline, TrackedRenderPass,
+        },
+        render_resource::*,
+        renderer::{RenderAdapterInfo, RenderDevice, RenderQueue},
+        texture::BevyDefault,
+        view::{ExtractedView, ViewTarget},
+        Extract, Render, RenderApp, RenderSet,
+    },
+    sprite::{Mesh2dPipeline, Mesh2dPipelineKey, SetMesh2dViewBindGroup},
+    utils::{FloatOrd, HashMap},
+};
+use bytemuck::{Pod, Zeroable};
+
+/// `0` keeps instancing off, `1` turns it on even on a software renderer.
+pub const INSTANCING_ENV: &str = "ALONE_INSTANCING";
+
+const INSTANCING_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 0x5a3c_91e2_07d4_4b6f);
+
+/// Whether prefabs are batched into one draw per mesh. Off without a GPU, software renderers
+/// do better with the regular per-entity meshes, which draw the same picture.
+#[derive(Resource)]
+pub struct Instancing {
+    pub enabled: bool,
+}
+
+/// Drawn batched with every other entity sharing the mesh, in the color of its
+/// `Handle<ColorMaterial>`. Takes the place of `Mesh2dHandle`, which would draw it again.
+#[derive(Component, Clone)]
+pub struct Instanced(pub Handle<Mesh>);
+
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct InstanceData {
+    x_axis: [f32; 4],
+    y_axis: [f32; 4],
+    translation: [f32; 4],
+    color: [f32; 4],
+}
+
+/// The instances of one mesh drawn this frame.
+struct MeshInstances {
+    buffer: BufferVec<InstanceData>,
+    /// The whole batch is sorted with the lowest instance.
+    z: f32,
+}
+
+/// Per mesh, a GPU buffer that is rewritten every frame and only reallocated when it has to
+/// grow.
+#[derive(Resource, Default)]
+struct InstanceBuffers(HashMap<Handle<Mesh>, MeshInstances>);
+
+/// Stands for one mesh's instances in the render phase.
+#[derive(Component)]
+struct InstanceBatch(Handle<Mesh>);
+
+fn extract_instances(
+    mut commands: Commands,
+    mut buffers: ResMut<InstanceBuffers>,
+    materials: Extract<Res<Assets<ColorMaterial>>>,
+    query: Extract<
+        Query<(
+            &Instanced,
+            &GlobalTransform,
+            &ComputedVisibility,
+            &Handle<ColorMaterial>,
+        )>,
+    >,
+) {
+    for instances in buffers.0.values_mut() {
+        instances.buffer.clear();
+        instances.z = f32::INFINITY;
+    }
+    for (instanced, transform, visibility, material) in &query {
+        if !visibility.is_visible_in_hierarchy() {
+            continue;
+        }
+        let color = materials.get(material).map_or(Color::WHITE, |m| m.color);
+        let m = transform.compute_matrix();
+        let instances = buffers
+            .0
+            .entry(instanced.0.clone_weak())
+            .or_insert_with(|| MeshInstances {
+                buffer: BufferVec::new(BufferUsages::VERTEX),
+                z: f32::INFINITY,
+            });
+        instances.z = instances.z.min(m.w_axis.z);
+        instances.buffer.push(InstanceData {
+            x_axis: m.x_axis.to_array(),
+            y_axis: m.y_axis.to_array(),
+            translation: m.w_axis.to_array(),
+            color: color.as_linear_rgba_f32(),
+        });
+    }
+    let batches: Vec<_> = buffers
+        .0
+        .iter()
+        .filter(|(_, instances)| !instances.buffer.is_empty())
+        .map(|(mesh, _)| InstanceBatch(mesh.clone_weak()))
+        .collect();
+    commands.spawn_batch(batches);
+}
+
+fn prepare_instance_buffers(
+    mut buffers: ResMut<InstanceBuffers>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    for instances in buffers.0.values_mut() {
+        instances.buffer.write_buffer(&render_device, &render_queue);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn queue_instances(
+    draw_functions: Res<DrawFunctions<Transparent2d>>,
+    instancing_pipeline: Res<InstancingPipeline>,
+    msaa: Res<Msaa>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<InstancingPipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    meshes: Res<RenderAssets<Mesh>>,
+    buffers: Res<InstanceBuffers>,
+    batches: Query<(Entity, &InstanceBatch)>,
+    mut views: Query<(&ExtractedView, &mut RenderPhase<Transparent2d>)>,
+) {
+    let draw = draw_functions.read().id::<DrawInstanced>();
+    for (view, mut phase) in &mut views {
+        let view_key = Mesh2dPipelineKey::from_msaa_samples(msaa.samples())
+            | Mesh2dPipelineKey::from_hdr(view.hdr);
+        for (entity, batch) in &batches {
+            let (Some(mesh), Some(instances)) = (meshes.get(&batch.0), buffers.0.get(&batch.0))
+            else {
+                continue;
+            };
+            let key =
+                view_key | Mesh2dPipelineKey::from_primitive_topology(mesh.primitive_topology);
+            let pipeline = match pipelines.specialize(
+                &pipeline_cache,
+                &instancing_pipeline,
+                key,
+                &mesh.layout,
+            ) {
+                Ok(pipeline) => pipeline,
+                Err(e) => {
+                    error!("{e}");
+                    continue;
+                }
+            };
+            phase.add(Transparent2d {
+                sort_key: FloatOrd(instances.z),
+                entity,
+                pipeline,
+                draw_function: draw,
+                batch_range: None,
+            });
+        }
+    }
+}
+
+#[derive(Resource)]
+struct InstancingPipeline {
+    view_layout: BindGroupLayout,
+}
+
+impl FromWorld for InstancingPipeline {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            view_layout: world.resource::<Mesh2dPipeline>().view_layout.clone(),
+        }
+    }
+}
+
+impl SpecializedMeshPipeline for InstancingPipeline {
+    type Key = Mesh2dPipelineKey;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayout,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let vertex = layout.get_layout(&[Mesh::ATTRIBUTE_POSITION.at_shader_location(0)])?;
+        let attribute = |i: u32| VertexAttribute {
+            format: VertexFormat::Float32x4,
+            offset: VertexFormat::Float32x4.size() * i as u64,
+            shader_location: i + 1,
+        };
+        let instance = VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceData>() as u64,
+            step_mode: VertexStepMode::Instance,
+            attributes: (0..4).map(attribute).collect(),
+        };
+        let format = match key.contains(Mesh2dPipelineKey::HDR) {
+            true => ViewTarget::TEXTURE_FORMAT_HDR,
+            false => TextureFormat::bevy_default(),
+        };
+        Ok(RenderPipelineDescriptor {
+            label: Some("instancing_pipeline".into()),
+            layout: vec![self.view_layout.clone()],
+            push_constant_ranges: Vec::new(),
+            vertex: VertexState {
+                shader: INSTANCING_SHADER_HANDLE.typed(),
+                shader_defs: Vec::new(),
+                entry_point: "vertex".into(),
+                buffers: vec![vertex, instance],
+            },
+            fragment: Some(FragmentState {
+                shader: INSTANCING_SHADER_HANDLE.typed(),
+                shader_defs: Vec::new(),
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState {
+                topology: key.primitive_topology(),
+                strip_index_format: None,
+                front_face: FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: key.msaa_samples(),
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+        })
+    }
+}
+
+type DrawInstanced = (
+    SetItemPipeline,
+    SetMesh2dViewBindGroup<0>,
+    DrawMeshInstanced,
+);
+
+struct DrawMeshInstanced;
+
+impl<P: PhaseItem> RenderCommand<P> for DrawMeshInstanced {
+    type Param = (SRes<RenderAssets<Mesh>>, SRes<InstanceBuffers>);
+    type ViewWorldQuery = ();
+    type ItemWorldQuery = Read<InstanceBatch>;
+
+    fn render<'w>(
+        _item: &P,
+        _view: ROQueryItem<'w, Self::ViewWorldQuery>,
+        batch: ROQueryItem<'w, Self::ItemWorldQuery>,
+        (meshes, buffers): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(mesh) = meshes.into_inner().get(&batch.0) else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(instances) = buffers.into_inner().0.get(&batch.0) else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(buffer) = instances.buffer.buffer() else {
+            return RenderCommandResult::Failure;
+        };
+        let length = instances.buffer.len() as u32;
+        pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+        pass.set_vertex_buffer(1, buffer.slice(..));
+        match &mesh.buffer_info {
+            GpuBufferInfo::Indexed {
+                buffer,
+                index_format,
+                count,
+            } => {
+                pass.set_index_buffer(buffer.slice(..), 0, *index_format);
+                pass.draw_indexed(0..*count, 0, 0..length);
+            }
+            GpuBufferInfo::NonIndexed => {
+                pass.draw(0..mesh.vertex_count, 0..length);
+            }
+        }
+        RenderCommandResult::Success
+    }
+}
+
+pub struct InstancingPlugin;
+
+impl Plugin for InstancingPlugin {
+    fn build(&self, app: &mut App) {
+        // decided in `finish`, once the renderer knows its adapter
+        app.insert_resource(Instancing { enabled: false });
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .add_render_command::<Transparent2d, DrawInstanced>()
+            .init_resource::<SpecializedMeshPipelines<InstancingPipeline>>()
+            .init_resource::<InstanceBuffers>()
+            .add_systems(ExtractSchedule, extract_instances)
+            .add_systems(
+                Render,
+                (
+                    prepare_instance_buffers.in_set(RenderSet::Prepare),
+                    queue_instances.in_set(RenderSet::Queue),
+                ),
+            );
+        load_internal_asset!(
+            app,
+            INSTANCING_SHADER_HANDLE,
+            "instancing.wgsl",
+            Shader::from_wgsl
+        );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let cpu = app
+            .world
+            .get_resource::<RenderAdapterInfo>()
+            .map(|info| info.device_type == wgpu::DeviceType::Cpu);
+        let enabled = match (std::env::var(INSTANCING_ENV).as_deref(), cpu) {
+            (_, None) | (Ok("0"), _) => false,
+            (Ok("1"), _) => true,
+            (_, Some(cpu)) => !cpu,
+        };
+        app.insert_resource(Instancing { enabled });
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.init_resource::<InstancingPipeline>();
+        }
+    }
+}
@@ -0,0 +1,34 @@
+// Draws every instance of a mesh in one call, each with its own transform and color.
+#import bevy_render::view View
+
+@group(0) @binding(0) var<uniform> view: View;
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    // the model matrix minus its z column, which flat meshes never read
+    @location(1) x_axis: vec4<f32>,
+    @location(2) y_axis: vec4<f32>,
+    @location(3) translation: vec4<f32>,
+    @location(4) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let world = vertex.x_axis * vertex.position.x
+        + vertex.y_axis * vertex.position.y
+        + vertex.translation;
+    var out: VertexOutput;
+    out.clip_position = view.view_proj * world;
+    out.color = vertex.color;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
@@ -0,0 +1,191 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    sprite::MaterialMesh2dBundle,
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::{Obstacle, ObstacleShape},
+    materials::ObstacleMaterial,
+    resources::{Arena, SpawnZone},
+    states::AppState,
+};
+
+pub const DEFAULT_LEVEL: &str = "levels/arena.level.ron";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ObstacleDef {
+    pub position: Vec2,
+    pub shape: ObstacleShape,
+}
+
+/// An arena as authored in `assets/levels/*.level.ron`. Missing fields fall back to the defaults.
+#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid, TypePath)]
+#[uuid = "d0b09453-e371-4da4-a44c-db28075411dd"]
+#[serde(default)]
+pub struct Level {
+    pub bounds: Vec2,
+    pub player_spawn: Vec2,
+    pub background: Color,
+    pub spawn_zones: Vec<SpawnZone>,
+    pub obstacles: Vec<ObstacleDef>,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        let arena = Arena::default();
+        Self {
+            bounds: arena.bounds,
+            player_spawn: arena.player_spawn,
+            background: Color::rgb(0.1, 0.1, 0.1),
+            spawn_zones: arena.spawn_zones,
+            obstacles: Vec::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let level: Level = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+/// The level played when entering `AppState::InGame`, `path` is relative to `assets/`.
+#[derive(Resource)]
+pub struct CurrentLevel {
+    pub handle: Handle<Level>,
+    pub path: String,
+}
+
+fn load_level(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(CurrentLevel {
+        handle: asset_server.load(DEFAULT_LEVEL),
+        path: DEFAULT_LEVEL.to_string(),
+    });
+}
+
+fn apply_arena(level: &Level, arena: &mut Arena, clear_color: &mut ClearColor) {
+    *arena = Arena {
+        bounds: level.bounds,
+        player_spawn: level.player_spawn,
+        spawn_zones: level.spawn_zones.clone(),
+    };
+    clear_color.0 = level.background;
+}
+
+fn respawn_obstacles(
+    commands: &mut Commands,
+    obstacles: &Query<Entity, With<Obstacle>>,
+    level: Option<&Level>,
+    meshes: &mut Assets<Mesh>,
+    material: &ObstacleMaterial,
+) {
+    for e in obstacles {
+        commands.entity(e).despawn_recursive();
+    }
+    let Some(level) = level else {
+        return;
+    };
+    for def in &level.obstacles {
+        let mesh: Mesh = match def.shape {
+            ObstacleShape::Rect { half_size } => shape::Quad::new(half_size * 2.0).into(),
+            ObstacleShape::Circle { radius } => shape::Circle::new(radius).into(),
+        };
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: meshes.add(mesh).into(),
+                material: material.0.clone(),
+                transform: Transform::from_translation(def.position.extend(-1.0)),
+                ..default()
+            },
+            Obstacle(def.shape),
+        ));
+    }
+}
+
+/// Applies the current level and spawns its obstacles. Runs before the player is spawned.
+#[allow(clippy::too_many_arguments)]
+pub fn setup_level(
+    mut commands: Commands,
+    current: Res<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+    mut arena: ResMut<Arena>,
+    mut clear_color: ResMut<ClearColor>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    material: Res<ObstacleMaterial>,
+    query: Query<Entity, With<Obstacle>>,
+) {
+    let level = levels.get(&current.handle);
+    if let Some(level) = level {
+        apply_arena(level, &mut arena, &mut clear_color);
+    }
+    respawn_obstacles(&mut commands, &query, level, &mut meshes, &material);
+}
+
+/// Re-applies the level when it finishes loading, is edited on disk or by the editor.
+#[allow(clippy::too_many_arguments)]
+fn reload_level(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<Level>>,
+    state: Res<State<AppState>>,
+    current: Res<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+    mut arena: ResMut<Arena>,
+    mut clear_color: ResMut<ClearColor>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    material: Res<ObstacleMaterial>,
+    query: Query<Entity, With<Obstacle>>,
+) {
+    let changed = events.iter().any(|ev| match ev {
+        AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+            *handle == current.handle
+        }
+        AssetEvent::Removed { .. } => false,
+    });
+    let Some(level) = levels.get(&current.handle).filter(|_| changed) else {
+        return;
+    };
+    apply_arena(level, &mut arena, &mut clear_color);
+    if matches!(state.get(), AppState::InGame | AppState::Editor) {
+        respawn_obstacles(&mut commands, &query, Some(level), &mut meshes, &material);
+    }
+}
+
+pub fn despawn_obstacles(mut commands: Commands, query: Query<Entity, With<Obstacle>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Level>()
+            .init_asset_loader::<LevelLoader>()
+            .init_resource::<Arena>()
+            .add_systems(Startup, load_level)
+            .add_systems(OnEnter(AppState::InGame), setup_level)
+            .add_systems(OnEnter(AppState::Editor), setup_level)
+            .add_systems(OnExit(AppState::Editor), despawn_obstacles)
+            .add_systems(Update, reload_level);
+    }
+}
@@ -1,9 +1,31 @@
+// bevy queries and filters nest generics by design
+#![allow(clippy::type_complexity)]
+
+pub mod audio;
+pub mod bot;
+pub mod camera;
 pub mod components;
+pub mod console;
+pub mod debug;
 pub mod diagnostics;
+pub mod editor;
+pub mod events;
+pub mod feedback;
+pub mod gameplay;
+pub mod hud;
+pub mod instancing;
+pub mod level;
 pub mod materials;
 pub mod meshes;
+pub mod navigation;
+pub mod particles;
 pub mod prefabs;
+pub mod profile;
 pub mod resources;
+pub mod save;
+pub mod sprites;
 pub mod states;
 pub mod systems;
+pub mod tuning;
 pub mod ui;
+pub mod upgrades;
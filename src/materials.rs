@@ -11,6 +11,9 @@ pub struct PlayerMaterial(pub MatHandle);
 #[derive(Resource)]
 pub struct EnemyMaterial(pub MatHandle);
 
+#[derive(Resource)]
+pub struct ObstacleMaterial(pub MatHandle);
+
 pub struct MyMaterialsPlugin;
 impl Plugin for MyMaterialsPlugin {
     fn build(&self, app: &mut App) {
@@ -25,6 +28,9 @@ fn store_materials(mut commands: Commands, mut materials: ResMut<Assets<ColorMat
     let enemy_handle = materials.add(Color::RED.into());
     info!("enemy_handle: {:?}", enemy_handle);
 
+    let obstacle_handle = materials.add(Color::rgb(0.3, 0.3, 0.35).into());
+
     commands.insert_resource(EnemyMaterial(enemy_handle));
     commands.insert_resource(BulletMaterial(bullet_handle));
+    commands.insert_resource(ObstacleMaterial(obstacle_handle));
 }
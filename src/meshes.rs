@@ -11,6 +11,10 @@ pub struct EnemyMesh(pub MeshHandle);
 #[derive(Resource)]
 pub struct PlayerMesh(pub MeshHandle);
 
+/// Unit quad scaled per particle.
+#[derive(Resource)]
+pub struct ParticleMesh(pub MeshHandle);
+
 pub struct MyMeshesPlugin;
 impl Plugin for MyMeshesPlugin {
     fn build(&self, app: &mut App) {
@@ -27,4 +31,7 @@ fn store_meshes(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
 
     let player_handle = meshes.add(shape::Quad::new(Vec2::splat(6.0)).into());
     commands.insert_resource(PlayerMesh(player_handle));
+
+    let particle_handle = meshes.add(shape::Quad::new(Vec2::ONE).into());
+    commands.insert_resource(ParticleMesh(particle_handle));
 }
@@ -0,0 +1,318 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use bevy::{math::Vec3Swizzles, prelude::*};
+use rand::Rng;
+
+use crate::{
+    components::{Dead, Obstacle, PathFollow, Player, Target},
+    diagnostics::timed,
+    resources::{Arena, GameRng},
+    states::{AppState, GameplaySet},
+    systems::{collision::penetration, steering},
+};
+
+/// Clearance kept between agents and obstacles when marking cells as blocked.
+const AGENT_RADIUS: f32 = 7.0;
+const REPATH_SECONDS: f32 = 0.5;
+
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+/// Walkability grid over the arena, rebuilt whenever obstacles change.
+#[derive(Resource)]
+pub struct NavGrid {
+    pub origin: Vec2,
+    pub cell_size: f32,
+    pub size: UVec2,
+    pub blocked: Vec<bool>,
+}
+
+impl NavGrid {
+    pub fn new(bounds: Vec2, cell_size: f32) -> Self {
+        let size = (bounds / cell_size).ceil().as_uvec2();
+        Self {
+            origin: -bounds / 2.0,
+            cell_size,
+            size,
+            blocked: vec![false; (size.x * size.y) as usize],
+        }
+    }
+
+    pub fn cell_of(&self, pos: Vec2) -> Option<UVec2> {
+        let cell = ((pos - self.origin) / self.cell_size).floor();
+        let inside = cell.cmpge(Vec2::ZERO).all() && cell.cmplt(self.size.as_vec2()).all();
+        inside.then(|| cell.as_uvec2())
+    }
+
+    pub fn center_of(&self, cell: UVec2) -> Vec2 {
+        self.origin + (cell.as_vec2() + 0.5) * self.cell_size
+    }
+
+    pub fn index(&self, cell: UVec2) -> usize {
+        (cell.y * self.size.x + cell.x) as usize
+    }
+
+    pub fn is_walkable(&self, cell: UVec2) -> bool {
+        !self.blocked[self.index(cell)]
+    }
+
+    /// Walkable neighbours of a cell with their move cost. Diagonals may not cut corners.
+    pub fn neighbours(&self, cell: UVec2) -> impl Iterator<Item = (UVec2, u32)> + '_ {
+        let c = cell.as_ivec2();
+        (-1..=1)
+            .flat_map(|x| (-1..=1).map(move |y| IVec2::new(x, y)))
+            .filter(|d| *d != IVec2::ZERO)
+            .filter_map(move |d| {
+                let n = c + d;
+                let inside = n.cmpge(IVec2::ZERO).all() && n.cmplt(self.size.as_ivec2()).all();
+                if !inside || !self.is_walkable(n.as_uvec2()) {
+                    return None;
+                }
+                if d.x != 0 && d.y != 0 {
+                    let side_a = IVec2::new(c.x + d.x, c.y).as_uvec2();
+                    let side_b = IVec2::new(c.x, c.y + d.y).as_uvec2();
+                    if !self.is_walkable(side_a) || !self.is_walkable(side_b) {
+                        return None;
+                    }
+                    return Some((n.as_uvec2(), DIAGONAL_COST));
+                }
+                Some((n.as_uvec2(), STRAIGHT_COST))
+            })
+    }
+
+    /// True if the straight segment between two points only crosses walkable cells.
+    pub fn line_of_sight(&self, from: Vec2, to: Vec2) -> bool {
+        let steps = (from.distance(to) / (self.cell_size * 0.5)).ceil().max(1.0) as u32;
+        (0..=steps).all(|i| {
+            let p = from.lerp(to, i as f32 / steps as f32);
+            self.cell_of(p).is_none_or(|c| self.is_walkable(c))
+        })
+    }
+
+    /// A* over the grid. Returns the cell centres to walk through, ending at `goal`.
+    pub fn find_path(&self, start: Vec2, goal: Vec2) -> Option<Vec<Vec2>> {
+        let start_cell = self.cell_of(start)?;
+        let goal_cell = self.cell_of(goal)?;
+        if !self.is_walkable(goal_cell) {
+            return None;
+        }
+
+        let heuristic = |c: UVec2| {
+            let d = (c.as_ivec2() - goal_cell.as_ivec2()).abs();
+            let (lo, hi) = (d.x.min(d.y) as u32, d.x.max(d.y) as u32);
+            DIAGONAL_COST * lo + STRAIGHT_COST * (hi - lo)
+        };
+
+        let mut cost = vec![u32::MAX; self.blocked.len()];
+        let mut came_from = vec![None; self.blocked.len()];
+        let mut open = BinaryHeap::new();
+        cost[self.index(start_cell)] = 0;
+        open.push(Reverse((heuristic(start_cell), start_cell.x, start_cell.y)));
+
+        while let Some(Reverse((_, x, y))) = open.pop() {
+            let cell = UVec2::new(x, y);
+            if cell == goal_cell {
+                let mut path = vec![goal];
+                let mut current = came_from[self.index(cell)];
+                while let Some(c) = current {
+                    if c == start_cell {
+                        break;
+                    }
+                    path.push(self.center_of(c));
+                    current = came_from[self.index(c)];
+                }
+                path.reverse();
+                return Some(path);
+            }
+            let current_cost = cost[self.index(cell)];
+            for (n, step) in self.neighbours(cell) {
+                let n_cost = current_cost + step;
+                let n_index = self.index(n);
+                if n_cost < cost[n_index] {
+                    cost[n_index] = n_cost;
+                    came_from[n_index] = Some(cell);
+                    open.push(Reverse((n_cost + heuristic(n), n.x, n.y)));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Default for NavGrid {
+    fn default() -> Self {
+        Self::new(Arena::default().bounds, 16.0)
+    }
+}
+
+/// Dijkstra map towards the player shared by the whole horde.
+/// Cells that can see the goal are left to regular steering.
+#[derive(Resource, Default)]
+pub struct FlowField {
+    pub goal: Option<Entity>,
+    pub goal_cell: Option<UVec2>,
+    pub cost: Vec<u32>,
+    pub direction: Vec<Vec2>,
+    pub visible: Vec<bool>,
+}
+
+impl FlowField {
+    pub fn compute(&mut self, grid: &NavGrid, goal: Vec2) {
+        let len = grid.blocked.len();
+        self.cost.clear();
+        self.cost.resize(len, u32::MAX);
+        self.direction.clear();
+        self.direction.resize(len, Vec2::ZERO);
+        self.visible.clear();
+        self.visible.resize(len, false);
+        self.goal_cell = grid.cell_of(goal);
+        let Some(goal_cell) = self.goal_cell else {
+            return;
+        };
+
+        let mut open = BinaryHeap::new();
+        self.cost[grid.index(goal_cell)] = 0;
+        open.push(Reverse((0, goal_cell.x, goal_cell.y)));
+        while let Some(Reverse((cell_cost, x, y))) = open.pop() {
+            let cell = UVec2::new(x, y);
+            if cell_cost > self.cost[grid.index(cell)] {
+                continue;
+            }
+            for (n, step) in grid.neighbours(cell) {
+                let n_cost = cell_cost + step;
+                let n_index = grid.index(n);
+                if n_cost < self.cost[n_index] {
+                    self.cost[n_index] = n_cost;
+                    open.push(Reverse((n_cost, n.x, n.y)));
+                }
+            }
+        }
+
+        for i in 0..len {
+            if self.cost[i] == u32::MAX {
+                continue;
+            }
+            let cell = UVec2::new(i as u32 % grid.size.x, i as u32 / grid.size.x);
+            let center = grid.center_of(cell);
+            self.visible[i] = grid.line_of_sight(center, goal);
+            let best = grid
+                .neighbours(cell)
+                .min_by_key(|(n, _)| self.cost[grid.index(*n)]);
+            if let Some((n, _)) = best {
+                if self.cost[grid.index(n)] < self.cost[i] {
+                    self.direction[i] = (grid.center_of(n) - center).normalize_or_zero();
+                }
+            }
+        }
+    }
+
+    /// Direction to follow from `pos`, or `None` where the goal can be steered to directly.
+    pub fn sample(&self, grid: &NavGrid, pos: Vec2) -> Option<Vec2> {
+        let index = grid.index(grid.cell_of(pos)?);
+        if self.visible.get(index).copied().unwrap_or(true) {
+            return None;
+        }
+        let dir = self.direction[index];
+        (dir != Vec2::ZERO).then_some(dir)
+    }
+}
+
+fn rebuild_nav_grid(
+    mut grid: ResMut<NavGrid>,
+    arena: Res<Arena>,
+    obstacles: Query<(&Transform, &Obstacle)>,
+    changed: Query<(), Changed<Obstacle>>,
+    mut removed: RemovedComponents<Obstacle>,
+) {
+    let any_removed = removed.iter().count() > 0;
+    if changed.is_empty() && !any_removed && !arena.is_changed() {
+        return;
+    }
+    if grid.size != (arena.bounds / grid.cell_size).ceil().as_uvec2() {
+        *grid = NavGrid::new(arena.bounds, grid.cell_size);
+    }
+    for i in 0..grid.blocked.len() {
+        let cell = UVec2::new(i as u32 % grid.size.x, i as u32 / grid.size.x);
+        let center = grid.center_of(cell);
+        grid.blocked[i] = obstacles.iter().any(|(o_t, o)| {
+            penetration(&o.0, o_t.translation.xy(), center, AGENT_RADIUS).is_some()
+        });
+    }
+}
+
+/// Recomputes the flow field when the player changes cells or the obstacles change.
+pub fn update_flow_field(
+    grid: Res<NavGrid>,
+    mut flow: ResMut<FlowField>,
+    player: Query<(Entity, &Transform), (With<Player>, Without<Dead>)>,
+) {
+    let Ok((e, t)) = player.get_single() else {
+        if flow.goal.is_some() {
+            *flow = FlowField::default();
+        }
+        return;
+    };
+    let pos = t.translation.xy();
+    if grid.is_changed() || flow.goal != Some(e) || flow.goal_cell != grid.cell_of(pos) {
+        flow.goal = Some(e);
+        flow.compute(&grid, pos);
+    }
+}
+
+/// Plans a path to the target when it is hidden behind obstacles and consumes reached waypoints.
+/// Agents chasing the flow field goal are skipped, they sample the field instead.
+fn path_request_system(
+    grid: Res<NavGrid>,
+    flow: Res<FlowField>,
+    mut query: Query<(&Transform, &Target, &mut PathFollow)>,
+    targets: Query<&Transform, Without<PathFollow>>,
+    mut rng: ResMut<GameRng>,
+    time: Res<Time>,
+) {
+    for (t, target, mut path) in &mut query {
+        if target.0.is_some() && target.0 == flow.goal {
+            if !path.waypoints.is_empty() {
+                path.waypoints.clear();
+            }
+            continue;
+        }
+        let pos = t.translation.xy();
+        path.repath_in -= time.delta_seconds();
+        if path.repath_in <= 0.0 {
+            // stagger the next request so the whole horde does not plan on the same frame
+            path.repath_in = REPATH_SECONDS * (0.5 + rng.0.gen::<f32>());
+            path.waypoints.clear();
+            if let Some(goal) = target.0.and_then(|e| targets.get(e).ok()) {
+                let goal = goal.translation.xy();
+                if !grid.line_of_sight(pos, goal) {
+                    path.waypoints = grid.find_path(pos, goal).unwrap_or_default();
+                }
+            }
+        }
+        while path.waypoints.len() > 1 && pos.distance(path.waypoints[0]) < grid.cell_size * 0.5 {
+            path.waypoints.remove(0);
+        }
+    }
+}
+
+pub struct NavigationPlugin;
+
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Arena>()
+            .init_resource::<NavGrid>()
+            .init_resource::<FlowField>()
+            .add_systems(
+                Update,
+                (
+                    timed("rebuild_nav_grid", rebuild_nav_grid),
+                    timed("flow_field", update_flow_field),
+                    timed("path_request", path_request_system),
+                )
+                    .chain()
+                    .before(steering::steering_system)
+                    .run_if(in_state(AppState::InGame))
+                    .in_set(GameplaySet),
+            );
+    }
+}
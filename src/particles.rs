@@ -0,0 +1,304 @@
+use std::ops::Range;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    events::{BulletImpact, EnemyKilled, PlayerDied, ShotFired},
+    instancing::Instancing,
+    meshes::ParticleMesh,
+    prefabs::{self, EntityPools, PoolAppExt, PoolKind},
+};
+
+/// Steps each color gradient is quantized into, so particles can share materials.
+const GRADIENT_STEPS: usize = 16;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ParticlePreset {
+    MuzzleFlash,
+    BulletImpact,
+    EnemyExplosion,
+    PlayerDeath,
+}
+
+impl ParticlePreset {
+    const ALL: [ParticlePreset; 4] = [
+        ParticlePreset::MuzzleFlash,
+        ParticlePreset::BulletImpact,
+        ParticlePreset::EnemyExplosion,
+        ParticlePreset::PlayerDeath,
+    ];
+
+    pub fn effect(self) -> ParticleEffect {
+        match self {
+            ParticlePreset::MuzzleFlash => ParticleEffect {
+                burst: 6,
+                rate: 0.0,
+                duration: 0.0,
+                lifetime: 0.05..0.12,
+                speed: 80.0..200.0,
+                spread: 0.35,
+                drag: 6.0,
+                size: (2.5, 0.5),
+                color: (Color::rgb(1.0, 0.95, 0.6), Color::rgba(1.0, 0.5, 0.0, 0.0)),
+            },
+            ParticlePreset::BulletImpact => ParticleEffect {
+                burst: 8,
+                rate: 0.0,
+                duration: 0.0,
+                lifetime: 0.1..0.25,
+                speed: 40.0..140.0,
+                spread: std::f32::consts::PI,
+                drag: 4.0,
+                size: (1.5, 0.5),
+                color: (Color::ORANGE, Color::rgba(0.4, 0.4, 0.4, 0.0)),
+            },
+            ParticlePreset::EnemyExplosion => ParticleEffect {
+                burst: 18,
+                rate: 0.0,
+                duration: 0.0,
+                lifetime: 0.25..0.6,
+                speed: 30.0..160.0,
+                spread: std::f32::consts::PI,
+                drag: 3.0,
+                size: (3.0, 0.5),
+                color: (Color::rgb(1.0, 0.8, 0.3), Color::rgba(0.6, 0.0, 0.0, 0.0)),
+            },
+            ParticlePreset::PlayerDeath => ParticleEffect {
+                burst: 30,
+                rate: 60.0,
+                duration: 1.5,
+                lifetime: 0.5..1.2,
+                speed: 20.0..120.0,
+                spread: std::f32::consts::PI,
+                drag: 1.5,
+                size: (3.0, 1.0),
+                color: (Color::WHITE, Color::rgba(0.3, 0.6, 1.0, 0.0)),
+            },
+        }
+    }
+}
+
+/// Describes how an emitter spawns particles. `spread` is the half angle around the emitter's up.
+#[derive(Clone, Debug)]
+pub struct ParticleEffect {
+    pub burst: u32,
+    /// Particles per second while the emitter is alive.
+    pub rate: f32,
+    pub duration: f32,
+    pub lifetime: Range<f32>,
+    pub speed: Range<f32>,
+    pub spread: f32,
+    pub drag: f32,
+    /// Size at birth and at death.
+    pub size: (f32, f32),
+    /// Color at birth and at death.
+    pub color: (Color, Color),
+}
+
+#[derive(Component)]
+pub struct ParticleEmitter {
+    pub preset: ParticlePreset,
+    pub elapsed: f32,
+    pub pending: f32,
+}
+
+#[derive(Component)]
+pub struct Particle {
+    pub preset: ParticlePreset,
+    pub velocity: Vec2,
+    pub lifetime: f32,
+    pub age: f32,
+    pub step: usize,
+}
+
+#[derive(Resource)]
+pub struct ParticleSettings {
+    /// Particles alive at once, new ones are dropped above this.
+    pub max_particles: usize,
+}
+
+impl Default for ParticleSettings {
+    fn default() -> Self {
+        Self {
+            max_particles: 4000,
+        }
+    }
+}
+
+/// Quantized color gradient of every preset.
+#[derive(Resource)]
+pub struct ParticleMaterials(pub Vec<(ParticlePreset, Vec<Handle<ColorMaterial>>)>);
+
+impl ParticleMaterials {
+    fn get(&self, preset: ParticlePreset, step: usize) -> Handle<ColorMaterial> {
+        let (_, steps) = self.0.iter().find(|(p, _)| *p == preset).unwrap();
+        steps[step.min(steps.len() - 1)].clone()
+    }
+}
+
+fn store_particle_materials(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    let gradients = ParticlePreset::ALL
+        .iter()
+        .map(|preset| {
+            let (from, to) = preset.effect().color;
+            let steps = (0..GRADIENT_STEPS)
+                .map(|i| {
+                    let t = i as f32 / (GRADIENT_STEPS - 1) as f32;
+                    let color = Color::from(Vec4::from(from).lerp(Vec4::from(to), t));
+                    materials.add(color.into())
+                })
+                .collect();
+            (*preset, steps)
+        })
+        .collect();
+    commands.insert_resource(ParticleMaterials(gradients));
+}
+
+/// Starts an effect at `transform`, pointing along its up vector.
+pub fn spawn_effect(commands: &mut Commands, preset: ParticlePreset, transform: Transform) {
+    commands.spawn((
+        TransformBundle::from_transform(transform),
+        ParticleEmitter {
+            preset,
+            elapsed: 0.0,
+            pending: preset.effect().burst as f32,
+        },
+    ));
+}
+
+fn effect_transform(position: Vec2, direction: Vec2) -> Transform {
+    let mut t = Transform::from_translation(position.extend(0.5));
+    if let Some(dir) = direction.try_normalize() {
+        t.rotation = Quat::from_rotation_arc_2d(Vec2::Y, dir);
+    }
+    t
+}
+
+fn trigger_effects(
+    mut commands: Commands,
+    mut shots: EventReader<ShotFired>,
+    mut impacts: EventReader<BulletImpact>,
+    mut kills: EventReader<EnemyKilled>,
+    mut deaths: EventReader<PlayerDied>,
+) {
+    for ev in shots.iter() {
+        let t = effect_transform(ev.position, ev.direction);
+        spawn_effect(&mut commands, ParticlePreset::MuzzleFlash, t);
+    }
+    for ev in impacts.iter() {
+        let t = effect_transform(ev.position, Vec2::ZERO);
+        spawn_effect(&mut commands, ParticlePreset::BulletImpact, t);
+    }
+    for ev in kills.iter() {
+        let t = effect_transform(ev.position, Vec2::ZERO);
+        spawn_effect(&mut commands, ParticlePreset::EnemyExplosion, t);
+    }
+    for ev in deaths.iter() {
+        let t = effect_transform(ev.position, Vec2::ZERO);
+        spawn_effect(&mut commands, ParticlePreset::PlayerDeath, t);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn emit_particles(
+    mut commands: Commands,
+    mut pools: ResMut<EntityPools>,
+    instancing: Res<Instancing>,
+    mut emitters: Query<(Entity, &Transform, &mut ParticleEmitter)>,
+    particles: Query<(), With<Particle>>,
+    settings: Res<ParticleSettings>,
+    mesh: Res<ParticleMesh>,
+    materials: Res<ParticleMaterials>,
+    time: Res<Time>,
+) {
+    let mut budget = settings
+        .max_particles
+        .saturating_sub(particles.iter().count());
+    let mut rng = rand::thread_rng();
+    for (e, t, mut emitter) in &mut emitters {
+        let effect = emitter.preset.effect();
+        emitter.elapsed += time.delta_seconds();
+        if emitter.elapsed <= effect.duration {
+            emitter.pending += effect.rate * time.delta_seconds();
+        }
+
+        let count = emitter.pending.floor() as usize;
+        emitter.pending -= count as f32;
+        for _ in 0..count.min(budget) {
+            let angle = rng.gen_range(-effect.spread..=effect.spread);
+            let dir = Vec2::from_angle(angle).rotate(t.up().truncate());
+            let size = effect.size.0;
+            let mut particle = pools.acquire(&mut commands, PoolKind::Particle);
+            prefabs::insert_visuals(
+                &mut particle,
+                &instancing,
+                &mesh.0,
+                materials.get(emitter.preset, 0),
+                Transform::from_translation(t.translation).with_scale(Vec3::new(size, size, 1.0)),
+            );
+            particle.insert(Particle {
+                preset: emitter.preset,
+                velocity: dir * rng.gen_range(effect.speed.clone()),
+                lifetime: rng.gen_range(effect.lifetime.clone()),
+                age: 0.0,
+                step: 0,
+            });
+        }
+        budget = budget.saturating_sub(count);
+
+        if emitter.elapsed > effect.duration {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+/// Moves particles and walks them along their size and color curves.
+fn update_particles(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &mut Particle,
+        &mut Transform,
+        &mut Handle<ColorMaterial>,
+    )>,
+    materials: Res<ParticleMaterials>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    for (e, mut p, mut t, mut material) in &mut query {
+        p.age += dt;
+        if p.age >= p.lifetime {
+            prefabs::release(&mut commands, e);
+            continue;
+        }
+        let effect = p.preset.effect();
+        let progress = p.age / p.lifetime;
+        p.velocity *= (1.0 - effect.drag * dt).max(0.0);
+        t.translation += (p.velocity * dt).extend(0.0);
+        let size = effect.size.0 + (effect.size.1 - effect.size.0) * progress;
+        t.scale = Vec3::new(size, size, 1.0);
+
+        let step = (progress * (GRADIENT_STEPS - 1) as f32).round() as usize;
+        if step != p.step {
+            p.step = step;
+            *material = materials.get(p.preset, step);
+        }
+    }
+}
+
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ParticleSettings>()
+            .on_release(|entity| {
+                entity.remove::<Particle>();
+            })
+            .add_systems(Startup, store_particle_materials)
+            .add_systems(
+                Update,
+                (trigger_effects, emit_particles, update_particles).chain(),
+            );
+    }
+}
@@ -1,29 +1,272 @@
-use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use bevy::{
+    ecs::{
+        system::{Command, EntityCommands},
+        world::EntityMut,
+    },
+    hierarchy::despawn_with_children_recursive,
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
 
-use crate::{materials::*, meshes::*};
+use crate::{
+    components::*,
+    instancing::{Instanced, Instancing},
+    materials::*,
+    meshes::*,
+    resources::ArchetypeStats,
+};
 
-pub fn bullet_bundle(
-    mesh: &Res<BulletMesh>,
-    material: &Res<BulletMaterial>,
-    transform: Transform,
-) -> MaterialMesh2dBundle<ColorMaterial> {
-    MaterialMesh2dBundle {
-        mesh: mesh.0.clone().into(),
-        material: material.0.clone(),
-        transform,
-        ..default()
+/// Entities recycled instead of being spawned and despawned all the time.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PoolKind {
+    Bullet,
+    Enemy,
+    Particle,
+}
+
+/// Owned by a pool. While `idle` the entity is hidden and keeps nothing but its visuals, so no
+/// gameplay query sees it.
+#[derive(Component)]
+pub struct Pooled {
+    pub kind: PoolKind,
+    pub idle: bool,
+}
+
+/// Idle entities of every kind, waiting to be acquired again.
+#[derive(Resource)]
+pub struct EntityPools {
+    /// Off, acquiring always spawns and releasing despawns, to compare against.
+    pub enabled: bool,
+    bullets: Vec<Entity>,
+    enemies: Vec<Entity>,
+    particles: Vec<Entity>,
+}
+
+impl Default for EntityPools {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            bullets: Vec::new(),
+            enemies: Vec::new(),
+            particles: Vec::new(),
+        }
+    }
+}
+
+impl EntityPools {
+    fn free(&mut self, kind: PoolKind) -> &mut Vec<Entity> {
+        match kind {
+            PoolKind::Bullet => &mut self.bullets,
+            PoolKind::Enemy => &mut self.enemies,
+            PoolKind::Particle => &mut self.particles,
+        }
+    }
+
+    pub fn idle(&self, kind: PoolKind) -> usize {
+        match kind {
+            PoolKind::Bullet => self.bullets.len(),
+            PoolKind::Enemy => self.enemies.len(),
+            PoolKind::Particle => self.particles.len(),
+        }
+    }
+
+    /// An idle entity of `kind`, or a new one when there is none. The caller inserts the
+    /// visuals and gameplay components, which also makes it visible again.
+    pub fn acquire<'w, 's, 'a>(
+        &mut self,
+        commands: &'a mut Commands<'w, 's>,
+        kind: PoolKind,
+    ) -> EntityCommands<'w, 's, 'a> {
+        let pooled = Pooled { kind, idle: false };
+        // skips entities despawned behind the pool's back
+        while let Some(e) = self.free(kind).pop() {
+            if commands.get_entity(e).is_some() {
+                let mut entity = commands.entity(e);
+                entity.insert(pooled);
+                return entity;
+            }
+        }
+        commands.spawn(pooled)
+    }
+}
+
+/// Hands a pooled entity back to its pool and despawns any other, children included. Safe to
+/// call more than once for the same entity in a frame.
+pub fn release(commands: &mut Commands, entity: Entity) {
+    commands.add(Release(entity));
+}
+
+/// Gameplay components a bullet, enemy or particle picks up in play, stripped when it goes idle.
+/// Other modules strip their own state with `on_release`.
+type InPlay = (
+    (Bullet, Pierce, Decay),
+    (
+        Enemy,
+        EnemyArchetype,
+        RotateToPlayer,
+        Target,
+        Steering,
+        PathFollow,
+        Idle,
+    ),
+    (Move, Sensor, Velocity, BoundaryBehavior),
+);
+
+/// Run on a pooled entity as it goes idle, after its children are despawned.
+pub type ReleaseHook = fn(&mut EntityMut);
+
+#[derive(Resource, Default)]
+struct ReleaseHooks(Vec<ReleaseHook>);
+
+pub trait PoolAppExt {
+    /// Adds `hook` to what releasing an entity strips, for state the pool does not know about.
+    fn on_release(&mut self, hook: ReleaseHook) -> &mut Self;
+}
+
+impl PoolAppExt for App {
+    fn on_release(&mut self, hook: ReleaseHook) -> &mut Self {
+        self.init_resource::<ReleaseHooks>();
+        self.world.resource_mut::<ReleaseHooks>().0.push(hook);
+        self
     }
 }
 
-pub fn enemy_bundle(
+struct Release(Entity);
+
+impl Command for Release {
+    fn apply(self, world: &mut World) {
+        if world.get_entity(self.0).is_none() {
+            return;
+        }
+        let enabled = world
+            .get_resource::<EntityPools>()
+            .is_some_and(|pools| pools.enabled);
+        let pooled = world.get::<Pooled>(self.0).map(|p| (p.kind, p.idle));
+        let kind = match pooled {
+            Some((_, true)) => return,
+            Some((kind, false)) if enabled => kind,
+            _ => {
+                despawn_with_children_recursive(world, self.0);
+                return;
+            }
+        };
+        let children = world
+            .get::<Children>(self.0)
+            .map(|c| c.to_vec())
+            .unwrap_or_default();
+        for child in children {
+            despawn_with_children_recursive(world, child);
+        }
+        world.resource_scope(|world, hooks: Mut<ReleaseHooks>| {
+            let mut entity = world.entity_mut(self.0);
+            entity.remove::<InPlay>();
+            for hook in &hooks.0 {
+                hook(&mut entity);
+            }
+            entity.insert((Visibility::Hidden, Pooled { kind, idle: true }));
+        });
+        world.resource_mut::<EntityPools>().free(kind).push(self.0);
+    }
+}
+
+/// Mesh, color and transform of a bullet, enemy or particle, batched with its peers when
+/// instancing is on.
+pub fn insert_visuals(
+    entity: &mut EntityCommands,
+    instancing: &Instancing,
+    mesh: &Handle<Mesh>,
+    material: Handle<ColorMaterial>,
+    transform: Transform,
+) {
+    entity.insert((material, SpatialBundle::from_transform(transform)));
+    match instancing.enabled {
+        true => entity.insert(Instanced(mesh.clone())),
+        false => entity.insert(Mesh2dHandle(mesh.clone())),
+    };
+}
+
+/// Hull and barrel of the player, added onto the already spawned `player`.
+pub fn add_player_visuals(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    player: Entity,
+    transform: Transform,
+) {
+    let barrel = commands
+        .spawn(MaterialMesh2dBundle {
+            mesh: meshes
+                .add(shape::Quad::new(Vec2::new(2.0, 3.0)).into())
+                .into(),
+            material: materials.add(Color::GRAY.into()),
+            transform: Transform::from_translation(Vec3::new(0.0, 1.5, 1.0)),
+            ..default()
+        })
+        .id();
+    commands
+        .entity(player)
+        .insert(MaterialMesh2dBundle {
+            mesh: meshes.add(shape::Quad::new(Vec2::splat(6.0)).into()).into(),
+            material: materials.add(Color::WHITE.into()),
+            transform,
+            ..default()
+        })
+        .add_child(barrel);
+}
+
+pub fn enemy_archetype_bundle(
+    archetype: EnemyArchetype,
+    stats: ArchetypeStats,
+) -> (EnemyArchetype, Move, RotateToPlayer, Sensor) {
+    (
+        archetype,
+        Move { speed: stats.speed },
+        RotateToPlayer {
+            speed: stats.turn_speed,
+        },
+        Sensor {
+            radius: stats.radius,
+        },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    pools: &mut EntityPools,
+    instancing: &Instancing,
     mesh: &Res<EnemyMesh>,
     material: &Res<EnemyMaterial>,
     transform: Transform,
-) -> MaterialMesh2dBundle<ColorMaterial> {
-    MaterialMesh2dBundle {
-        mesh: mesh.0.clone().into(),
-        material: material.0.clone(),
+    archetype: EnemyArchetype,
+    stats: ArchetypeStats,
+) -> Entity {
+    let mut enemy = pools.acquire(commands, PoolKind::Enemy);
+    insert_visuals(
+        &mut enemy,
+        instancing,
+        &mesh.0,
+        material.0.clone(),
         transform,
-        ..default()
+    );
+    enemy
+        .insert((
+            Enemy,
+            enemy_archetype_bundle(archetype, stats),
+            Target::default(),
+            Steering::default(),
+            PathFollow::default(),
+            Velocity::default(),
+            BoundaryBehavior::Clamp,
+        ))
+        .id()
+}
+
+pub struct PoolPlugin;
+
+impl Plugin for PoolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EntityPools>()
+            .init_resource::<ReleaseHooks>();
     }
 }
@@ -0,0 +1,433 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::{Modifier, ModifierOp, PlayerStats, Stat},
+    level::{CurrentLevel, DEFAULT_LEVEL},
+    resources::Score,
+    states::AppState,
+};
+
+/// Where the profile lives, relative to the working directory like the level editor's saves.
+pub const PROFILE_PATH: &str = "profile.ron";
+/// Bump when the schema changes in a way `#[serde(default)]` cannot absorb, keeping the old schema
+/// as its own struct that converts into the next one.
+pub const PROFILE_VERSION: u32 = 1;
+/// Highest level a perk can be bought to.
+pub const MAX_PERK_LEVEL: u32 = 5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum WeaponKind {
+    Pistol,
+    SpreadGun,
+    Railgun,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Character {
+    Runner,
+    Tank,
+    Scout,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum ArenaKind {
+    Arena,
+    Pillars,
+}
+
+/// Permanent upgrades, each level stacks on every run.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub enum Perk {
+    Vitality,
+    Swiftness,
+    RapidFire,
+}
+
+impl WeaponKind {
+    pub const ALL: [WeaponKind; 3] = [
+        WeaponKind::Pistol,
+        WeaponKind::SpreadGun,
+        WeaponKind::Railgun,
+    ];
+
+    pub fn modifiers(self) -> Vec<Modifier> {
+        let m = |stat, op| Modifier { stat, op };
+        match self {
+            WeaponKind::Pistol => vec![],
+            WeaponKind::SpreadGun => vec![
+                m(Stat::BulletCount, ModifierOp::Add(2.0)),
+                m(Stat::FireRate, ModifierOp::Mul(0.8)),
+            ],
+            WeaponKind::Railgun => vec![
+                m(Stat::BulletPierce, ModifierOp::Add(2.0)),
+                m(Stat::BulletSpeed, ModifierOp::Mul(1.5)),
+                m(Stat::FireRate, ModifierOp::Mul(0.6)),
+            ],
+        }
+    }
+}
+
+impl Character {
+    fn modifiers(self) -> Vec<Modifier> {
+        let m = |stat, op| Modifier { stat, op };
+        match self {
+            Character::Runner => vec![],
+            Character::Tank => vec![
+                m(Stat::MaxHealth, ModifierOp::Add(3.0)),
+                m(Stat::MoveSpeed, ModifierOp::Mul(0.85)),
+            ],
+            Character::Scout => vec![
+                m(Stat::MoveSpeed, ModifierOp::Mul(1.3)),
+                m(Stat::MaxHealth, ModifierOp::Add(-1.0)),
+            ],
+        }
+    }
+}
+
+impl ArenaKind {
+    pub fn path(self) -> &'static str {
+        match self {
+            ArenaKind::Arena => DEFAULT_LEVEL,
+            ArenaKind::Pillars => "levels/pillars.level.ron",
+        }
+    }
+}
+
+impl Perk {
+    fn modifier(self, level: u32) -> Modifier {
+        let level = level as f32;
+        match self {
+            Perk::Vitality => Modifier {
+                stat: Stat::MaxHealth,
+                op: ModifierOp::Add(level),
+            },
+            Perk::Swiftness => Modifier {
+                stat: Stat::MoveSpeed,
+                op: ModifierOp::Mul(1.0 + 0.05 * level),
+            },
+            Perk::RapidFire => Modifier {
+                stat: Stat::FireRate,
+                op: ModifierOp::Mul(1.0 + 0.05 * level),
+            },
+        }
+    }
+}
+
+/// What the next run starts with.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Loadout {
+    pub weapon: WeaponKind,
+    pub character: Character,
+    pub arena: ArenaKind,
+}
+
+impl Default for Loadout {
+    fn default() -> Self {
+        Self {
+            weapon: WeaponKind::Pistol,
+            character: Character::Runner,
+            arena: ArenaKind::Arena,
+        }
+    }
+}
+
+/// Progress kept across runs. New fields need a default so older files keep loading.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub version: u32,
+    pub currency: u32,
+    pub runs: u32,
+    pub best_score: u32,
+    pub weapons: Vec<WeaponKind>,
+    pub characters: Vec<Character>,
+    pub arenas: Vec<ArenaKind>,
+    pub perks: BTreeMap<Perk, u32>,
+    pub loadout: Loadout,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            version: PROFILE_VERSION,
+            currency: 0,
+            runs: 0,
+            best_score: 0,
+            weapons: vec![WeaponKind::Pistol],
+            characters: vec![Character::Runner],
+            arenas: vec![ArenaKind::Arena],
+            perks: BTreeMap::new(),
+            loadout: Loadout::default(),
+        }
+    }
+}
+
+/// Only the version, read first to pick the schema to parse.
+#[derive(Deserialize)]
+struct VersionProbe {
+    #[serde(default)]
+    version: u32,
+}
+
+/// Version 0, saved before profiles carried a version.
+#[derive(Deserialize)]
+#[serde(default)]
+struct ProfileV0 {
+    currency: u32,
+    runs: u32,
+    best_score: u32,
+    weapons: Vec<WeaponKind>,
+    characters: Vec<Character>,
+    arenas: Vec<ArenaKind>,
+    perks: BTreeMap<Perk, u32>,
+    loadout: Loadout,
+}
+
+impl Default for ProfileV0 {
+    fn default() -> Self {
+        let p = Profile::default();
+        Self {
+            currency: p.currency,
+            runs: p.runs,
+            best_score: p.best_score,
+            weapons: p.weapons,
+            characters: p.characters,
+            arenas: p.arenas,
+            perks: p.perks,
+            loadout: p.loadout,
+        }
+    }
+}
+
+impl From<ProfileV0> for Profile {
+    fn from(v0: ProfileV0) -> Self {
+        Self {
+            version: 1,
+            currency: v0.currency,
+            runs: v0.runs,
+            best_score: v0.best_score,
+            weapons: v0.weapons,
+            characters: v0.characters,
+            arenas: v0.arenas,
+            perks: v0.perks,
+            loadout: v0.loadout,
+        }
+    }
+}
+
+/// Currency paid out for a run that ended with `score`.
+pub fn currency_for(score: u32) -> u32 {
+    score / 10
+}
+
+impl Profile {
+    /// Parses a profile of any known version, migrating it to the current schema.
+    pub fn from_ron(text: &str) -> Result<Profile, String> {
+        fn parse<'a, T: Deserialize<'a>>(text: &'a str) -> Result<T, String> {
+            ron::de::from_str(text).map_err(|e| e.to_string())
+        }
+        // a missing version reads as 0, the first schema
+        match parse::<VersionProbe>(text)?.version {
+            0 => parse::<ProfileV0>(text).map(Profile::from),
+            PROFILE_VERSION => parse::<Profile>(text),
+            v => Err(format!(
+                "unknown profile version {v}, this build reads up to {PROFILE_VERSION}"
+            )),
+        }
+    }
+
+    pub fn load() -> Profile {
+        let Ok(text) = std::fs::read_to_string(PROFILE_PATH) else {
+            return Profile::default();
+        };
+        match Profile::from_ron(&text) {
+            Ok(profile) => profile,
+            Err(e) => {
+                // keep the unreadable file around instead of overwriting it on the next save
+                let backup = format!("{PROFILE_PATH}.bak");
+                error!("failed to load {PROFILE_PATH}: {e}, moved it to {backup}");
+                let _ = std::fs::rename(PROFILE_PATH, backup);
+                Profile::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let tmp = format!("{PROFILE_PATH}.tmp");
+        let saved = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())
+            .and_then(|s| std::fs::write(&tmp, s).map_err(|e| e.to_string()))
+            .and_then(|_| std::fs::rename(&tmp, PROFILE_PATH).map_err(|e| e.to_string()));
+        if let Err(e) = saved {
+            error!("failed to save {PROFILE_PATH}: {e}");
+        }
+    }
+
+    pub fn perk_level(&self, perk: Perk) -> u32 {
+        self.perks.get(&perk).copied().unwrap_or(0)
+    }
+
+    /// Stats a new run starts with: the loadout and every perk level on top of the defaults.
+    pub fn starting_stats(&self) -> PlayerStats {
+        let mut stats = PlayerStats::default();
+        stats.modifiers.extend(self.loadout.weapon.modifiers());
+        stats.modifiers.extend(self.loadout.character.modifiers());
+        for (perk, level) in &self.perks {
+            stats.modifiers.push(perk.modifier(*level));
+        }
+        stats
+    }
+}
+
+/// Everything the shop sells.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShopItem {
+    Weapon(WeaponKind),
+    Character(Character),
+    Arena(ArenaKind),
+    Perk(Perk),
+}
+
+/// How a shop item relates to the profile.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ItemStatus {
+    Selected,
+    Owned,
+    Buy(u32),
+    Maxed,
+}
+
+impl ShopItem {
+    pub const ALL: [ShopItem; 11] = [
+        ShopItem::Weapon(WeaponKind::Pistol),
+        ShopItem::Weapon(WeaponKind::SpreadGun),
+        ShopItem::Weapon(WeaponKind::Railgun),
+        ShopItem::Character(Character::Runner),
+        ShopItem::Character(Character::Tank),
+        ShopItem::Character(Character::Scout),
+        ShopItem::Arena(ArenaKind::Arena),
+        ShopItem::Arena(ArenaKind::Pillars),
+        ShopItem::Perk(Perk::Vitality),
+        ShopItem::Perk(Perk::Swiftness),
+        ShopItem::Perk(Perk::RapidFire),
+    ];
+
+    pub fn label(self) -> String {
+        match self {
+            ShopItem::Weapon(w) => format!("{w:?}"),
+            ShopItem::Character(c) => format!("{c:?}"),
+            ShopItem::Arena(a) => format!("{a:?}"),
+            ShopItem::Perk(p) => format!("{p:?}"),
+        }
+    }
+
+    fn base_cost(self) -> u32 {
+        match self {
+            ShopItem::Weapon(WeaponKind::Pistol)
+            | ShopItem::Character(Character::Runner)
+            | ShopItem::Arena(ArenaKind::Arena) => 0,
+            ShopItem::Weapon(_) => 150,
+            ShopItem::Character(_) => 100,
+            ShopItem::Arena(_) => 80,
+            ShopItem::Perk(_) => 40,
+        }
+    }
+
+    pub fn status(self, profile: &Profile) -> ItemStatus {
+        let loadout = &profile.loadout;
+        let (owned, selected) = match self {
+            ShopItem::Weapon(w) => (profile.weapons.contains(&w), loadout.weapon == w),
+            ShopItem::Character(c) => (profile.characters.contains(&c), loadout.character == c),
+            ShopItem::Arena(a) => (profile.arenas.contains(&a), loadout.arena == a),
+            ShopItem::Perk(p) => {
+                // perks get pricier with every level
+                let level = profile.perk_level(p);
+                return match level >= MAX_PERK_LEVEL {
+                    true => ItemStatus::Maxed,
+                    false => ItemStatus::Buy(self.base_cost() * (level + 1)),
+                };
+            }
+        };
+        match (owned, selected) {
+            (_, true) => ItemStatus::Selected,
+            (true, false) => ItemStatus::Owned,
+            (false, false) => ItemStatus::Buy(self.base_cost()),
+        }
+    }
+}
+
+impl Profile {
+    /// Buys the item if it can be afforded, or equips it if it is already owned.
+    /// Returns whether anything changed.
+    pub fn activate(&mut self, item: ShopItem) -> bool {
+        match item.status(self) {
+            ItemStatus::Selected | ItemStatus::Maxed => return false,
+            ItemStatus::Buy(cost) if cost > self.currency => return false,
+            ItemStatus::Buy(cost) => {
+                self.currency -= cost;
+                match item {
+                    ShopItem::Weapon(w) => self.weapons.push(w),
+                    ShopItem::Character(c) => self.characters.push(c),
+                    ShopItem::Arena(a) => self.arenas.push(a),
+                    ShopItem::Perk(p) => {
+                        *self.perks.entry(p).or_default() += 1;
+                        return true;
+                    }
+                }
+            }
+            ItemStatus::Owned => {}
+        }
+        match item {
+            ShopItem::Weapon(w) => self.loadout.weapon = w,
+            ShopItem::Character(c) => self.loadout.character = c,
+            ShopItem::Arena(a) => self.loadout.arena = a,
+            ShopItem::Perk(_) => {}
+        }
+        true
+    }
+}
+
+fn load_profile(mut commands: Commands) {
+    commands.insert_resource(Profile::load());
+}
+
+/// Pays out the run's currency and records it.
+fn finish_run(mut profile: ResMut<Profile>, score: Res<Score>) {
+    profile.currency += currency_for(score.0);
+    profile.runs += 1;
+    profile.best_score = profile.best_score.max(score.0);
+    profile.save();
+}
+
+/// Points the current level at the arena picked in the loadout.
+fn sync_arena(
+    profile: Res<Profile>,
+    asset_server: Res<AssetServer>,
+    current: Option<ResMut<CurrentLevel>>,
+) {
+    let Some(mut current) = current else {
+        return;
+    };
+    let path = profile.loadout.arena.path();
+    if current.path != path {
+        current.handle = asset_server.load(path);
+        current.path = path.to_string();
+    }
+}
+
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, load_profile)
+            .add_systems(OnEnter(AppState::GameOver), finish_run)
+            .add_systems(
+                Update,
+                sync_arena.run_if(in_state(AppState::Menu).or_else(in_state(AppState::Shop))),
+            );
+    }
+}
@@ -1,14 +1,306 @@
-use bevy::prelude::*;
+use std::sync::atomic::{AtomicU32, Ordering};
 
-pub const BOUNDS: Vec2 = Vec2::new(1200.0, 640.0);
+use bevy::{prelude::*, utils::HashMap};
+use bevy_inspector_egui::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 
-#[derive(Resource, Default)]
+use crate::components::EnemyArchetype;
+
+/// Rectangle enemies may be spawned in.
+#[derive(Reflect, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct SpawnZone {
+    pub center: Vec2,
+    pub half_size: Vec2,
+}
+
+/// Layout of the level being played, kept in sync with the `Level` asset.
+#[derive(Resource, Reflect, Clone)]
+#[reflect(Resource)]
+pub struct Arena {
+    pub bounds: Vec2,
+    pub player_spawn: Vec2,
+    pub spawn_zones: Vec<SpawnZone>,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self {
+            bounds: Vec2::new(1200.0, 640.0),
+            player_spawn: Vec2::ZERO,
+            spawn_zones: Vec::new(),
+        }
+    }
+}
+
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
 pub struct MouseWorldPos(pub Vec2);
 
-#[derive(Resource)]
+/// What the player is asked to do this frame, filled from the keyboard and mouse or by a bot.
+#[derive(Resource, Default)]
+pub struct PlayerInput {
+    pub movement: Vec2,
+    /// World position to aim at, if any.
+    pub aim: Option<Vec2>,
+    pub fire: bool,
+    /// Index into the upgrade draft on offer.
+    pub pick: Option<usize>,
+}
+
+/// Where the current round is in its lifecycle.
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum RoundPhase {
+    /// The banner is up, the wave spawns once the countdown runs out.
+    #[default]
+    WarmUp,
+    /// Fighting until every enemy is dead or the countdown runs out.
+    Active,
+    /// The round is over, `cleared` tells whether the player killed everything.
+    Cleared,
+    /// Waiting for the player to pick an upgrade.
+    Intermission,
+}
+
+#[derive(Resource, Reflect, InspectorOptions, Default)]
+#[reflect(Resource, Default, InspectorOptions)]
 pub struct RoundParams {
+    #[inspector(min = 1)]
     pub round: u32,
+    /// Seconds an active round lasts at most.
+    #[inspector(min = 1.0, max = 300.0)]
     pub length: f32,
+    /// Seconds left in the current phase.
+    #[inspector(min = 0.0)]
     pub countdown: f32,
+    #[inspector(max = 500)]
     pub enemies: u32,
+    pub phase: RoundPhase,
+    pub cleared: bool,
+    /// Bonus awarded for the last clear.
+    pub bonus: u32,
+}
+
+/// Points earned this run.
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource, Default)]
+pub struct Score(pub u32);
+
+/// Sensor pairs the collision systems tested this frame. Atomic so they keep running in parallel.
+#[derive(Resource, Default)]
+pub struct CollisionStats {
+    pairs: AtomicU32,
+}
+
+impl CollisionStats {
+    pub fn add(&self, pairs: u32) {
+        self.pairs.fetch_add(pairs, Ordering::Relaxed);
+    }
+
+    /// Pairs counted since the last call.
+    pub fn take(&self) -> u32 {
+        self.pairs.swap(0, Ordering::Relaxed)
+    }
+}
+
+/// Seeded generator behind every gameplay roll. Saved with the run, so a resumed run rolls the
+/// same spawns and drafts it would have rolled.
+#[derive(Resource, Reflect, Clone, Serialize, Deserialize)]
+#[reflect_value(Resource, Default, Serialize, Deserialize)]
+pub struct GameRng(pub ChaCha8Rng);
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self(ChaCha8Rng::from_entropy())
+    }
+}
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self(ChaCha8Rng::seed_from_u64(seed))
+    }
+}
+
+/// Seeds the `GameRng` of the next runs instead of entropy, for runs that can be replayed.
+#[derive(Resource)]
+pub struct RunSeed(pub u64);
+
+/// How the first round is set up and how fast the next ones grow.
+#[derive(Resource, Reflect, InspectorOptions, Clone, Serialize, Deserialize)]
+#[reflect(Resource, InspectorOptions)]
+pub struct RoundConfig {
+    #[inspector(min = 1.0, max = 300.0)]
+    pub length: f32,
+    #[inspector(min = 1, max = 500)]
+    pub first_enemies: u32,
+    /// Enemy count multiplier from one round to the next.
+    #[inspector(min = 1.0, max = 3.0)]
+    pub enemy_growth: f32,
+}
+
+impl Default for RoundConfig {
+    fn default() -> Self {
+        Self {
+            length: 30.0,
+            first_enemies: 10,
+            enemy_growth: 1.2,
+        }
+    }
+}
+
+/// Movement and size of one enemy archetype.
+#[derive(Reflect, InspectorOptions, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[reflect(InspectorOptions)]
+pub struct ArchetypeStats {
+    #[inspector(min = 0.0, max = 500.0)]
+    pub speed: f32,
+    #[inspector(min = 0.0, max = 720.0)]
+    pub turn_speed: f32,
+    #[inspector(min = 1.0, max = 50.0)]
+    pub radius: f32,
+}
+
+#[derive(Resource, Reflect, Clone)]
+#[reflect(Resource)]
+pub struct ArchetypeConfig(pub HashMap<EnemyArchetype, ArchetypeStats>);
+
+impl Default for ArchetypeConfig {
+    fn default() -> Self {
+        let stats = |speed, turn_speed, radius| ArchetypeStats {
+            speed,
+            turn_speed,
+            radius,
+        };
+        Self(HashMap::from_iter([
+            (EnemyArchetype::Grunt, stats(50.0, 180.0, 7.0)),
+            (EnemyArchetype::Hunter, stats(70.0, 240.0, 6.0)),
+            (EnemyArchetype::Swarmer, stats(60.0, 180.0, 5.0)),
+        ]))
+    }
+}
+
+/// Weights and tuning of every steering behavior for one enemy archetype.
+#[derive(Reflect, InspectorOptions, Clone, Copy, Serialize, Deserialize)]
+#[reflect(InspectorOptions)]
+pub struct SteeringProfile {
+    #[inspector(min = 0.0, max = 5.0)]
+    pub seek: f32,
+    #[inspector(min = 0.0, max = 5.0)]
+    pub flee: f32,
+    #[inspector(min = 0.0, max = 5.0)]
+    pub arrive: f32,
+    #[inspector(min = 0.0, max = 5.0)]
+    pub pursue: f32,
+    #[inspector(min = 0.0, max = 5.0)]
+    pub wander: f32,
+    #[inspector(min = 0.0, max = 5.0)]
+    pub separation: f32,
+    #[inspector(min = 0.0, max = 5.0)]
+    pub alignment: f32,
+    #[inspector(min = 0.0, max = 5.0)]
+    pub cohesion: f32,
+    #[inspector(min = 0.0, max = 400.0)]
+    pub neighbour_radius: f32,
+    #[inspector(min = 0.0, max = 400.0)]
+    pub separation_radius: f32,
+    #[inspector(min = 0.0, max = 400.0)]
+    pub flee_radius: f32,
+    #[inspector(min = 0.0, max = 400.0)]
+    pub slowing_radius: f32,
+    #[inspector(min = 0.0, max = 5.0)]
+    pub max_prediction: f32,
+    #[inspector(min = 0.0, max = 20.0)]
+    pub wander_jitter: f32,
+}
+
+impl Default for SteeringProfile {
+    fn default() -> Self {
+        Self {
+            seek: 0.0,
+            flee: 0.0,
+            arrive: 0.0,
+            pursue: 0.0,
+            wander: 0.0,
+            separation: 0.0,
+            alignment: 0.0,
+            cohesion: 0.0,
+            neighbour_radius: 40.0,
+            separation_radius: 16.0,
+            flee_radius: 0.0,
+            slowing_radius: 100.0,
+            max_prediction: 1.0,
+            wander_jitter: 3.0,
+        }
+    }
+}
+
+#[derive(Resource, Reflect, Clone)]
+#[reflect(Resource)]
+pub struct SteeringConfig(pub HashMap<EnemyArchetype, SteeringProfile>);
+
+impl Default for SteeringConfig {
+    fn default() -> Self {
+        let mut profiles = HashMap::new();
+        profiles.insert(
+            EnemyArchetype::Grunt,
+            SteeringProfile {
+                seek: 1.0,
+                wander: 0.2,
+                separation: 1.5,
+                ..default()
+            },
+        );
+        profiles.insert(
+            EnemyArchetype::Hunter,
+            SteeringProfile {
+                pursue: 1.0,
+                wander: 0.1,
+                separation: 1.0,
+                max_prediction: 1.5,
+                ..default()
+            },
+        );
+        profiles.insert(
+            EnemyArchetype::Swarmer,
+            SteeringProfile {
+                seek: 0.6,
+                wander: 0.3,
+                separation: 0.8,
+                alignment: 0.5,
+                cohesion: 0.4,
+                neighbour_radius: 60.0,
+                ..default()
+            },
+        );
+        Self(profiles)
+    }
+}
+
+#[derive(Resource, Reflect, InspectorOptions, Clone, Serialize, Deserialize)]
+#[reflect(Resource, InspectorOptions)]
+pub struct SpawnSettings {
+    /// Enemies never appear closer than this to a player.
+    #[inspector(min = 0.0, max = 600.0)]
+    pub min_player_distance: f32,
+    /// Chance of spawning along the arena edge instead of in a spawn zone.
+    #[inspector(min = 0.0, max = 1.0)]
+    pub edge_chance: f32,
+    /// Distance from the arena edge used for edge spawns.
+    #[inspector(min = 0.0, max = 200.0)]
+    pub edge_inset: f32,
+    /// How long the telegraph is shown before the enemy appears.
+    #[inspector(min = 0.05, max = 5.0)]
+    pub telegraph_seconds: f32,
+}
+
+impl Default for SpawnSettings {
+    fn default() -> Self {
+        Self {
+            min_player_distance: 160.0,
+            edge_chance: 0.3,
+            edge_inset: 20.0,
+            telegraph_seconds: 0.8,
+        }
+    }
 }
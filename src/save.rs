@@ -0,0 +1,269 @@
+use bevy::{
+    app::AppExit, ecs::entity::EntityMap, input::common_conditions::input_just_pressed, prelude::*,
+    scene::serde::SceneDeserializer,
+};
+use serde::de::DeserializeSeed;
+
+use crate::{
+    components::*,
+    instancing::Instancing,
+    materials::{BulletMaterial, EnemyMaterial},
+    meshes::{BulletMesh, EnemyMesh},
+    prefabs,
+    resources::{GameRng, RoundParams, RoundPhase, Score},
+    states::AppState,
+    upgrades::{Draft, RunSummary},
+};
+
+/// Where the run in progress is kept, next to the profile.
+pub const SAVE_PATH: &str = "savegame.scn.ron";
+
+/// Entities that make up a run. The level, camera and HUD are rebuilt on their own.
+type RunEntity = Or<(
+    With<Player>,
+    With<Enemy>,
+    With<Bullet>,
+    With<SpawnTelegraph>,
+)>;
+
+/// Swaps the freshly set up run for the saved one on the first frame in game.
+#[derive(Resource)]
+pub struct ResumeRun;
+
+/// Written from a save and still missing the meshes and materials, which are not saved.
+#[derive(Component)]
+struct Restored;
+
+pub fn has_save() -> bool {
+    std::path::Path::new(SAVE_PATH).exists()
+}
+
+/// Snapshot of the run. `Target` is left out, it points at entities of this world and is
+/// reacquired on the next frame anyway.
+fn build_scene(world: &mut World) -> DynamicScene {
+    let entities: Vec<Entity> = world
+        .query_filtered::<Entity, RunEntity>()
+        .iter(world)
+        .collect();
+    let mut builder = DynamicSceneBuilder::from_world(world);
+    builder
+        .allow::<Transform>()
+        .allow::<Player>()
+        .allow::<Enemy>()
+        .allow::<Bullet>()
+        .allow::<SpawnTelegraph>()
+        .allow::<Health>()
+        .allow::<MaxHealth>()
+        .allow::<HitCooldown>()
+        .allow::<Weapon>()
+        .allow::<PlayerStats>()
+        .allow::<Targetable>()
+        .allow::<Move>()
+        .allow::<RotateToPlayer>()
+        .allow::<Decay>()
+        .allow::<Pierce>()
+        .allow::<Sensor>()
+        .allow::<Velocity>()
+        .allow::<Idle>()
+        .allow::<EnemyArchetype>()
+        .allow::<Steering>()
+        .allow::<PathFollow>()
+        .allow::<BoundaryBehavior>()
+        .allow_resource::<RoundParams>()
+        .allow_resource::<Score>()
+        .allow_resource::<RunSummary>()
+        .allow_resource::<Draft>()
+        .allow_resource::<GameRng>()
+        .extract_entities(entities.into_iter())
+        .extract_resources();
+    builder.build()
+}
+
+fn save_run(world: &mut World) {
+    let alive = world
+        .query_filtered::<(), (With<Player>, Without<Dead>)>()
+        .iter(world)
+        .next()
+        .is_some();
+    if !alive {
+        return;
+    }
+    let scene = build_scene(world);
+    let tmp = format!("{SAVE_PATH}.tmp");
+    let saved = scene
+        .serialize_ron(world.resource::<AppTypeRegistry>())
+        .map_err(|e| e.to_string())
+        .and_then(|s| std::fs::write(&tmp, s).map_err(|e| e.to_string()))
+        .and_then(|_| std::fs::rename(&tmp, SAVE_PATH).map_err(|e| e.to_string()));
+    match saved {
+        Ok(()) => info!("saved the run to {SAVE_PATH}"),
+        Err(e) => error!("failed to save {SAVE_PATH}: {e}"),
+    }
+}
+
+fn load_scene(world: &World) -> Result<DynamicScene, String> {
+    let text = std::fs::read_to_string(SAVE_PATH).map_err(|e| e.to_string())?;
+    let registry = world.resource::<AppTypeRegistry>().read();
+    let mut deserializer = ron::de::Deserializer::from_str(&text).map_err(|e| e.to_string())?;
+    SceneDeserializer {
+        type_registry: &registry,
+    }
+    .deserialize(&mut deserializer)
+    .map_err(|e| e.to_string())
+}
+
+/// Replaces the run `setup_game` just started with the saved entities and resources.
+fn restore_run(world: &mut World) {
+    world.remove_resource::<ResumeRun>();
+    let scene = match load_scene(world) {
+        Ok(scene) => scene,
+        Err(e) => {
+            error!("failed to load {SAVE_PATH}: {e}");
+            return;
+        }
+    };
+    let fresh: Vec<Entity> = world
+        .query_filtered::<Entity, RunEntity>()
+        .iter(world)
+        .collect();
+    for e in fresh {
+        world.entity_mut(e).despawn_recursive();
+    }
+    let mut entity_map = EntityMap::default();
+    if let Err(e) = scene.write_to_world(world, &mut entity_map) {
+        error!("failed to restore {SAVE_PATH}: {e}");
+        return;
+    }
+    for e in entity_map.values() {
+        world.entity_mut(e).insert(Restored);
+    }
+    info!("resumed round {}", world.resource::<RoundParams>().round);
+}
+
+/// Gives restored entities back what their prefabs would have spawned them with.
+#[allow(clippy::too_many_arguments)]
+fn rehydrate(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    instancing: Res<Instancing>,
+    enemy_mesh: Res<EnemyMesh>,
+    enemy_mat: Res<EnemyMaterial>,
+    bullet_mesh: Res<BulletMesh>,
+    bullet_mat: Res<BulletMaterial>,
+    query: Query<
+        (
+            Entity,
+            &Transform,
+            Option<&Player>,
+            Option<&Enemy>,
+            Option<&Bullet>,
+        ),
+        With<Restored>,
+    >,
+) {
+    for (e, t, player, enemy, bullet) in &query {
+        commands.entity(e).remove::<Restored>();
+        match (player, enemy, bullet) {
+            (Some(_), _, _) => {
+                prefabs::add_player_visuals(&mut commands, &mut meshes, &mut materials, e, *t)
+            }
+            (_, Some(_), _) => {
+                let mut enemy = commands.entity(e);
+                prefabs::insert_visuals(
+                    &mut enemy,
+                    &instancing,
+                    &enemy_mesh.0,
+                    enemy_mat.0.clone(),
+                    *t,
+                );
+                enemy.insert(Target::default());
+            }
+            (_, _, Some(_)) => prefabs::insert_visuals(
+                &mut commands.entity(e),
+                &instancing,
+                &bullet_mesh.0,
+                bullet_mat.0.clone(),
+                *t,
+            ),
+            // telegraphs are drawn with gizmos
+            _ => {
+                commands
+                    .entity(e)
+                    .insert(TransformBundle::from_transform(*t));
+            }
+        }
+    }
+}
+
+/// A finished run cannot be resumed.
+fn delete_save() {
+    if has_save() {
+        if let Err(e) = std::fs::remove_file(SAVE_PATH) {
+            error!("failed to delete {SAVE_PATH}: {e}");
+        }
+    }
+}
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Player>()
+            .register_type::<God>()
+            .register_type::<Enemy>()
+            .register_type::<Bullet>()
+            .register_type::<Dead>()
+            .register_type::<SpawnTelegraph>()
+            .register_type::<Health>()
+            .register_type::<MaxHealth>()
+            .register_type::<HitCooldown>()
+            .register_type::<Weapon>()
+            .register_type::<Stat>()
+            .register_type::<ModifierOp>()
+            .register_type::<Modifier>()
+            .register_type::<Vec<Modifier>>()
+            .register_type::<bevy::utils::HashMap<Stat, f32>>()
+            .register_type::<PlayerStats>()
+            .register_type::<Targetable>()
+            .register_type::<Target>()
+            .register_type::<Move>()
+            .register_type::<RotateToPlayer>()
+            .register_type::<Decay>()
+            .register_type::<Pierce>()
+            .register_type::<Sensor>()
+            .register_type::<Velocity>()
+            .register_type::<Idle>()
+            .register_type::<EnemyArchetype>()
+            .register_type::<Steering>()
+            .register_type::<PathFollow>()
+            .register_type::<Vec<Vec2>>()
+            .register_type::<BoundaryBehavior>()
+            .register_type::<ObstacleShape>()
+            .register_type::<Obstacle>()
+            .register_type::<RoundPhase>()
+            .register_type::<RoundParams>()
+            .register_type::<Score>()
+            .register_type::<RunSummary>()
+            .register_type::<Vec<String>>()
+            .register_type::<Draft>()
+            .register_type::<Vec<usize>>()
+            .register_type::<GameRng>()
+            .add_systems(
+                Update,
+                (
+                    save_run.run_if(input_just_pressed(KeyCode::F5)),
+                    restore_run.run_if(resource_exists::<ResumeRun>()),
+                    rehydrate,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            // closing the window mid-run keeps the run
+            .add_systems(
+                Last,
+                save_run.run_if(in_state(AppState::InGame).and_then(on_event::<AppExit>())),
+            )
+            .add_systems(OnEnter(AppState::GameOver), delete_save);
+    }
+}
@@ -0,0 +1,421 @@
+use std::path::PathBuf;
+
+use bevy::{
+    asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    sprite::Mesh2dHandle,
+    utils::{BoxedFuture, HashMap},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::{Dead, EnemyArchetype, Player, Velocity},
+    events::EnemyKilled,
+    feedback::HitFlash,
+    instancing::Instanced,
+    prefabs::PoolAppExt,
+    states::{AppState, GameplaySet},
+};
+
+pub const DEFAULT_SPRITES: &str = "sprites/default.sprites.ron";
+
+/// Slower than this counts as standing still.
+const MOVING_SPEED: f32 = 5.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Clip {
+    Idle,
+    Move,
+    Hit,
+    Death,
+}
+
+impl Clip {
+    /// Hit and death play once and hold their last frame.
+    fn looping(self) -> bool {
+        matches!(self, Clip::Idle | Clip::Move)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ClipDef {
+    /// Atlas indices, row by row from the top left.
+    pub frames: Vec<usize>,
+    pub fps: f32,
+}
+
+/// One sprite sheet as authored in `assets/sprites/*.sprites.ron`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SheetDef {
+    /// Relative to `assets/`.
+    pub image: String,
+    pub tile_size: Vec2,
+    pub columns: usize,
+    pub rows: usize,
+    #[serde(default)]
+    pub padding: Option<Vec2>,
+    /// Size drawn in the world, independent of the image resolution.
+    pub size: Vec2,
+    /// Mirrors the sprite while walking left, for art drawn facing right.
+    #[serde(default)]
+    pub flip: bool,
+    /// Keeps the sprite from turning with its entity.
+    #[serde(default)]
+    pub upright: bool,
+    /// A missing clip falls back to `Idle`.
+    pub clips: HashMap<Clip, ClipDef>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SheetDefs {
+    player: Option<SheetDef>,
+    enemies: HashMap<EnemyArchetype, SheetDef>,
+}
+
+pub struct SpriteSheet {
+    pub atlas: Handle<TextureAtlas>,
+    pub def: SheetDef,
+}
+
+impl SpriteSheet {
+    fn clip(&self, clip: Clip) -> Option<&ClipDef> {
+        self.def
+            .clips
+            .get(&clip)
+            .or_else(|| self.def.clips.get(&Clip::Idle))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SheetKey {
+    Player,
+    Enemy(EnemyArchetype),
+}
+
+/// The art of everything that has some. Anything without a sheet keeps its placeholder quad.
+#[derive(TypeUuid, TypePath)]
+#[uuid = "6b1f0c8e-2a47-4f53-9d2e-71c4a8e0b5d3"]
+pub struct SpriteSheets {
+    pub player: Option<SpriteSheet>,
+    pub enemies: HashMap<EnemyArchetype, SpriteSheet>,
+}
+
+impl SpriteSheets {
+    pub fn get(&self, key: SheetKey) -> Option<&SpriteSheet> {
+        match key {
+            SheetKey::Player => self.player.as_ref(),
+            SheetKey::Enemy(archetype) => self.enemies.get(&archetype),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SpriteSheetsLoader;
+
+/// Cuts the sheet's image into a texture atlas, stored under `label` next to the sheets.
+fn load_sheet(load_context: &mut LoadContext, label: &str, def: SheetDef) -> SpriteSheet {
+    let image = AssetPath::new(PathBuf::from(&def.image), None);
+    let atlas = TextureAtlas::from_grid(
+        load_context.get_handle(image.clone()),
+        def.tile_size,
+        def.columns,
+        def.rows,
+        def.padding,
+        None,
+    );
+    let atlas =
+        load_context.set_labeled_asset(label, LoadedAsset::new(atlas).with_dependency(image));
+    SpriteSheet { atlas, def }
+}
+
+impl AssetLoader for SpriteSheetsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let defs: SheetDefs = ron::de::from_bytes(bytes)?;
+            let player = defs
+                .player
+                .map(|def| load_sheet(load_context, "player", def));
+            let enemies = defs
+                .enemies
+                .into_iter()
+                .map(|(archetype, def)| {
+                    let label = format!("{archetype:?}");
+                    (archetype, load_sheet(load_context, &label, def))
+                })
+                .collect();
+            load_context.set_default_asset(LoadedAsset::new(SpriteSheets { player, enemies }));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sprites.ron"]
+    }
+}
+
+#[derive(Resource)]
+pub struct Sprites(pub Handle<SpriteSheets>);
+
+/// Plays the clips of a sheet on a sprite, picked from the state of its parent.
+#[derive(Component)]
+pub struct SpriteAnimation {
+    pub sheet: SheetKey,
+    pub clip: Clip,
+    /// Position within the clip's frames.
+    pub frame: usize,
+    pub elapsed: f32,
+    pub finished: bool,
+}
+
+impl SpriteAnimation {
+    fn new(sheet: SheetKey, clip: Clip) -> Self {
+        Self {
+            sheet,
+            clip,
+            frame: 0,
+            elapsed: 0.0,
+            finished: false,
+        }
+    }
+
+    /// Starts `clip` over, unless it is already playing.
+    fn play(&mut self, clip: Clip) {
+        if self.clip != clip {
+            *self = Self::new(self.sheet, clip);
+        }
+    }
+}
+
+/// Looked at for a sheet already, whether it had one or not. Pools drop it on release, so
+/// recycled entities are looked at again.
+#[derive(Component)]
+pub struct Sprited;
+
+/// A killed enemy playing its death clip, gone once it is over.
+#[derive(Component)]
+pub struct Corpse;
+
+fn load_sprites(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Sprites(asset_server.load(DEFAULT_SPRITES)));
+}
+
+fn sprite_bundle(sheet: &SpriteSheet, clip: Clip, transform: Transform) -> SpriteSheetBundle {
+    let index = sheet
+        .clip(clip)
+        .and_then(|c| c.frames.first().copied())
+        .unwrap_or_default();
+    SpriteSheetBundle {
+        sprite: TextureAtlasSprite {
+            index,
+            custom_size: Some(sheet.def.size),
+            ..default()
+        },
+        texture_atlas: sheet.atlas.clone(),
+        transform,
+        ..default()
+    }
+}
+
+/// Swaps the quads of entities that have a sheet for an animated sprite child.
+fn attach_sprites(
+    mut commands: Commands,
+    sprites: Res<Sprites>,
+    sheets: Res<Assets<SpriteSheets>>,
+    query: Query<
+        (Entity, Option<&EnemyArchetype>),
+        (
+            Or<(With<Player>, With<EnemyArchetype>)>,
+            // not before the prefab gave it its quad
+            Or<(With<Mesh2dHandle>, With<Instanced>)>,
+            Without<Sprited>,
+        ),
+    >,
+    children: Query<&Children>,
+) {
+    let Some(sheets) = sheets.get(&sprites.0) else {
+        return;
+    };
+    for (e, archetype) in &query {
+        commands.entity(e).insert(Sprited);
+        let key = match archetype {
+            Some(archetype) => SheetKey::Enemy(*archetype),
+            None => SheetKey::Player,
+        };
+        let Some(sheet) = sheets.get(key) else {
+            continue;
+        };
+        for part in std::iter::once(e).chain(children.iter_descendants(e)) {
+            commands.entity(part).remove::<(Mesh2dHandle, Instanced)>();
+        }
+        let sprite = commands
+            .spawn((
+                sprite_bundle(sheet, Clip::Idle, Transform::default()),
+                SpriteAnimation::new(key, Clip::Idle),
+            ))
+            .id();
+        commands.entity(e).add_child(sprite);
+    }
+}
+
+/// Picks each sprite's clip from its parent's movement, hits and death, and faces it.
+fn pick_clips(
+    sprites: Res<Sprites>,
+    sheets: Res<Assets<SpriteSheets>>,
+    mut query: Query<(
+        &Parent,
+        &mut SpriteAnimation,
+        &mut TextureAtlasSprite,
+        &mut Transform,
+    )>,
+    owners: Query<
+        (
+            &Transform,
+            Option<&Velocity>,
+            Option<&Dead>,
+            Option<Ref<HitFlash>>,
+        ),
+        Without<SpriteAnimation>,
+    >,
+) {
+    let Some(sheets) = sheets.get(&sprites.0) else {
+        return;
+    };
+    for (parent, mut animation, mut sprite, mut t) in &mut query {
+        let Ok((owner, velocity, dead, flash)) = owners.get(parent.get()) else {
+            continue;
+        };
+        let Some(sheet) = sheets.get(animation.sheet) else {
+            continue;
+        };
+        let velocity = velocity.map_or(Vec2::ZERO, |v| v.0);
+        let clip = if dead.is_some() {
+            Clip::Death
+        } else if flash.is_some_and(|f| f.is_added())
+            || (animation.clip == Clip::Hit && !animation.finished)
+        {
+            Clip::Hit
+        } else if velocity.length() > MOVING_SPEED {
+            Clip::Move
+        } else {
+            Clip::Idle
+        };
+        animation.play(clip);
+        if sheet.def.flip && velocity.x.abs() > MOVING_SPEED {
+            sprite.flip_x = velocity.x < 0.0;
+        }
+        if sheet.def.upright {
+            t.rotation = owner.rotation.inverse();
+        }
+    }
+}
+
+fn advance_frames(
+    sprites: Res<Sprites>,
+    sheets: Res<Assets<SpriteSheets>>,
+    mut query: Query<(&mut SpriteAnimation, &mut TextureAtlasSprite)>,
+    time: Res<Time>,
+) {
+    let Some(sheets) = sheets.get(&sprites.0) else {
+        return;
+    };
+    for (mut animation, mut sprite) in &mut query {
+        let Some(clip) = sheets
+            .get(animation.sheet)
+            .and_then(|s| s.clip(animation.clip))
+        else {
+            continue;
+        };
+        let step = 1.0 / clip.fps.max(f32::EPSILON);
+        animation.elapsed += time.delta_seconds();
+        while animation.elapsed >= step && !animation.finished {
+            animation.elapsed -= step;
+            if animation.frame + 1 < clip.frames.len() {
+                animation.frame += 1;
+            } else if animation.clip.looping() {
+                animation.frame = 0;
+            } else {
+                animation.finished = true;
+            }
+        }
+        if let Some(&index) = clip.frames.get(animation.frame) {
+            if sprite.index != index {
+                sprite.index = index;
+            }
+        }
+    }
+}
+
+/// Killed enemies are recycled right away, a corpse stays behind for their death clip.
+fn spawn_corpses(
+    mut commands: Commands,
+    sprites: Res<Sprites>,
+    sheets: Res<Assets<SpriteSheets>>,
+    mut kills: EventReader<EnemyKilled>,
+) {
+    let Some(sheets) = sheets.get(&sprites.0) else {
+        kills.clear();
+        return;
+    };
+    for kill in kills.iter() {
+        let key = SheetKey::Enemy(kill.archetype);
+        let Some(sheet) = sheets.get(key) else {
+            continue;
+        };
+        if !sheet.def.clips.contains_key(&Clip::Death) {
+            continue;
+        }
+        let t = Transform::from_translation(kill.position.extend(-0.5));
+        commands.spawn((
+            sprite_bundle(sheet, Clip::Death, t),
+            SpriteAnimation::new(key, Clip::Death),
+            Corpse,
+        ));
+    }
+}
+
+fn remove_corpses(mut commands: Commands, query: Query<(Entity, &SpriteAnimation), With<Corpse>>) {
+    for (e, animation) in &query {
+        if animation.finished {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+fn cleanup_corpses(mut commands: Commands, query: Query<Entity, With<Corpse>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+pub struct SpritesPlugin;
+
+impl Plugin for SpritesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<SpriteSheets>()
+            .on_release(|entity| {
+                entity.remove::<Sprited>();
+            })
+            .init_asset_loader::<SpriteSheetsLoader>()
+            .add_systems(Startup, load_sprites)
+            .add_systems(
+                Update,
+                (
+                    attach_sprites,
+                    spawn_corpses,
+                    pick_clips,
+                    advance_frames,
+                    remove_corpses,
+                )
+                    .chain()
+                    .after(GameplaySet)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), cleanup_corpses);
+    }
+}
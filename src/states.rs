@@ -6,11 +6,22 @@ pub enum AppState {
     Menu,
     InGame,
     GameOver,
+    Editor,
+    Shop,
 }
 
+/// Systems that advance the simulation, held back together by the debug frame step.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameplaySet;
+
+/// Systems filling `PlayerInput`, ahead of everything that reads it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlayerInputSet;
+
 pub struct StatesPlugin;
 impl Plugin for StatesPlugin {
     fn build(&self, app: &mut App) {
-        app.add_state::<AppState>();
+        app.add_state::<AppState>()
+            .configure_set(Update, PlayerInputSet.before(GameplaySet));
     }
 }
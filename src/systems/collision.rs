@@ -1,38 +1,75 @@
 use bevy::prelude::*;
 
-use crate::components::{Bullet, Enemy, Health, HitCooldown, Player, Sensor};
+use crate::{
+    components::{
+        Bullet, Enemy, EnemyArchetype, God, Health, HitCooldown, Move, Obstacle, ObstacleShape,
+        Pierce, Player, Sensor,
+    },
+    events::{BulletImpact, EnemyKilled, PlayerHit},
+    prefabs,
+    resources::CollisionStats,
+};
 
 pub fn bullet_enemy(
     mut commands: Commands,
-    q_bullets: Query<(Entity, &Transform, &Sensor), With<Bullet>>,
-    q_enemies: Query<(Entity, &Transform, &Sensor), With<Enemy>>,
+    mut q_bullets: Query<(Entity, &Transform, &Sensor, Option<&mut Pierce>), With<Bullet>>,
+    q_enemies: Query<(Entity, &Transform, &Sensor, &EnemyArchetype), With<Enemy>>,
+    mut killed: EventWriter<EnemyKilled>,
+    mut killed_this_frame: Local<Vec<Entity>>,
+    stats: Res<CollisionStats>,
 ) {
-    for (b_e, b_t, b_s) in &q_bullets {
-        for (e_e, e_t, e_s) in &q_enemies {
+    killed_this_frame.clear();
+    let mut pairs = 0;
+    for (b_e, b_t, b_s, mut pierce) in &mut q_bullets {
+        for (e_e, e_t, e_s, archetype) in &q_enemies {
+            if killed_this_frame.contains(&e_e) {
+                continue;
+            }
+            pairs += 1;
             let mut colided = false;
             if e_t.translation.distance(b_t.translation) < (b_s.radius + e_s.radius) {
                 colided = true;
-                commands.entity(e_e).despawn_recursive();
-                commands.entity(b_e).despawn_recursive();
+                prefabs::release(&mut commands, e_e);
+                match pierce.as_deref_mut() {
+                    Some(Pierce(left)) if *left > 0 => *left -= 1,
+                    _ => prefabs::release(&mut commands, b_e),
+                }
+                killed_this_frame.push(e_e);
+                killed.send(EnemyKilled {
+                    position: e_t.translation.truncate(),
+                    archetype: *archetype,
+                });
             }
             if colided {
                 break;
             }
         }
     }
+    stats.add(pairs);
 }
 
 pub fn player_enemy(
-    mut player: Query<(Entity, &Transform, &Sensor, &mut Health, &mut HitCooldown), With<Player>>,
+    mut player: Query<
+        (Entity, &Transform, &Sensor, &mut Health, &mut HitCooldown),
+        (With<Player>, Without<God>),
+    >,
     q_enemies: Query<(&Transform, &Sensor), With<Enemy>>,
+    mut hits: EventWriter<PlayerHit>,
+    stats: Res<CollisionStats>,
     time: Res<Time>,
 ) {
-    if let Ok((_p_e, b_t, b_s, mut p_h, mut p_cd)) = player.get_single_mut() {
+    if let Ok((p_e, b_t, b_s, mut p_h, mut p_cd)) = player.get_single_mut() {
         if p_cd.time_remains <= 0.0 {
+            stats.add(q_enemies.iter().len() as u32);
             for (e_t, e_s) in &q_enemies {
                 if e_t.translation.distance(b_t.translation) < (b_s.radius + e_s.radius) {
                     p_cd.time_remains = p_cd.time_full;
                     p_h.0 -= 1.0;
+                    hits.send(PlayerHit {
+                        entity: p_e,
+                        position: b_t.translation.truncate(),
+                        damage: 1.0,
+                    });
                 }
             }
         } else {
@@ -40,3 +77,68 @@ pub fn player_enemy(
         }
     }
 }
+
+/// Vector that pushes a circle at `p` out of an obstacle centred at `center`, if they overlap.
+pub fn penetration(shape: &ObstacleShape, center: Vec2, p: Vec2, radius: f32) -> Option<Vec2> {
+    let local = p - center;
+    match *shape {
+        ObstacleShape::Circle { radius: r } => {
+            let depth = r + radius - local.length();
+            (depth > 0.0).then(|| local.try_normalize().unwrap_or(Vec2::Y) * depth)
+        }
+        ObstacleShape::Rect { half_size } => {
+            let outside = local - local.clamp(-half_size, half_size);
+            let dist = outside.length();
+            if dist > 0.0 {
+                let depth = radius - dist;
+                return (depth > 0.0).then(|| outside / dist * depth);
+            }
+            // the centre is inside the box, leave through the nearest side
+            let to_edge = half_size - local.abs();
+            match to_edge.x < to_edge.y {
+                true => Some(Vec2::new((to_edge.x + radius) * local.x.signum(), 0.0)),
+                false => Some(Vec2::new(0.0, (to_edge.y + radius) * local.y.signum())),
+            }
+        }
+    }
+}
+
+pub fn obstacle_blocking(
+    mut movers: Query<(&mut Transform, &Sensor), (With<Move>, Without<Bullet>, Without<Obstacle>)>,
+    obstacles: Query<(&Transform, &Obstacle)>,
+    stats: Res<CollisionStats>,
+) {
+    let mut pairs = 0;
+    for (mut m_t, m_s) in &mut movers {
+        for (o_t, o) in &obstacles {
+            pairs += 1;
+            let m_pos = m_t.translation.truncate();
+            if let Some(push) = penetration(&o.0, o_t.translation.truncate(), m_pos, m_s.radius) {
+                m_t.translation += push.extend(0.0);
+            }
+        }
+    }
+    stats.add(pairs);
+}
+
+pub fn bullet_obstacle(
+    mut commands: Commands,
+    q_bullets: Query<(Entity, &Transform, &Sensor), With<Bullet>>,
+    obstacles: Query<(&Transform, &Obstacle)>,
+    mut impacts: EventWriter<BulletImpact>,
+    stats: Res<CollisionStats>,
+) {
+    let mut pairs = 0;
+    for (b_e, b_t, b_s) in &q_bullets {
+        let b_pos = b_t.translation.truncate();
+        let hit = obstacles.iter().any(|(o_t, o)| {
+            pairs += 1;
+            penetration(&o.0, o_t.translation.truncate(), b_pos, b_s.radius).is_some()
+        });
+        if hit {
+            prefabs::release(&mut commands, b_e);
+            impacts.send(BulletImpact { position: b_pos });
+        }
+    }
+    stats.add(pairs);
+}
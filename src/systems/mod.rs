@@ -1,3 +1,7 @@
 pub mod collision;
 pub mod movement;
 pub mod player;
+pub mod rounds;
+pub mod spawning;
+pub mod steering;
+pub mod targeting;
@@ -1,64 +1,156 @@
-use crate::components::*;
+use crate::{
+    components::*,
+    prefabs,
+    resources::{Arena, GameRng},
+};
 use bevy::{math::Vec3Swizzles, prelude::*};
+use rand::Rng;
 
-pub fn move_system(mut query: Query<(&Move, &mut Transform), Without<Player>>, time: Res<Time>) {
-    for (m, mut t) in &mut query {
-        let mv_vector = t.up() * m.speed * time.delta_seconds();
-        t.translation += mv_vector;
+pub fn move_system(
+    mut query: Query<
+        (
+            &Move,
+            &mut Transform,
+            Option<&Target>,
+            Option<&Idle>,
+            Option<&Steering>,
+            Option<&mut Velocity>,
+        ),
+        Without<Player>,
+    >,
+    time: Res<Time>,
+) {
+    for (m, mut t, target, idle, steering, velocity) in &mut query {
+        let has_target = target.is_none_or(|t| t.0.is_some());
+        if !has_target && matches!(idle, Some(Idle::Stand)) {
+            continue;
+        }
+        // steered entities ease off the throttle when their behaviors cancel out
+        let throttle = steering.map_or(1.0, |s| s.desired.length());
+        let velocity_vector = t.up() * m.speed * throttle;
+        t.translation += velocity_vector * time.delta_seconds();
+        if let Some(mut v) = velocity {
+            v.0 = velocity_vector.xy();
+        }
     }
 }
 
 pub fn rotate_to_player_system(
-    mut query: Query<(&RotateToPlayer, &mut Transform), Without<Player>>,
-    player_query: Query<&Transform, With<Player>>,
+    mut query: Query<
+        (
+            &RotateToPlayer,
+            &Target,
+            Option<&Idle>,
+            Option<&Steering>,
+            &mut Transform,
+        ),
+        Without<Targetable>,
+    >,
+    target_query: Query<&Transform, With<Targetable>>,
+    mut rng: ResMut<GameRng>,
     time: Res<Time>,
 ) {
-    if let Ok(player_transform) = player_query.get_single() {
-        // get the player translation in 2D
-        let player_translation = player_transform.translation.xy();
+    for (config, target, idle, steering, mut enemy_transform) in &mut query {
+        // steer towards the blended steering direction if there is one, otherwise straight at the
+        // target, or wander around while there is nothing to chase
+        let desired = match steering {
+            Some(s) => s.desired,
+            None => match target.0.and_then(|e| target_query.get(e).ok()) {
+                Some(target_transform) => {
+                    target_transform.translation.xy() - enemy_transform.translation.xy()
+                }
+                None => {
+                    if let Some(Idle::Wander { turn_speed }) = idle {
+                        let jitter = rng.0.gen::<f32>() * 2.0 - 1.0;
+                        enemy_transform.rotate_z(jitter * turn_speed * time.delta_seconds());
+                    }
+                    continue;
+                }
+            },
+        };
 
-        for (config, mut enemy_transform) in &mut query {
-            // get the enemy ship forward vector in 2D (already unit length)
-            let enemy_forward = (enemy_transform.rotation * Vec3::Y).xy();
+        // get the enemy ship forward vector in 2D (already unit length)
+        let enemy_forward = (enemy_transform.rotation * Vec3::Y).xy();
 
-            // get the vector from the enemy ship to the player ship in 2D and normalize it.
-            let to_player = (player_translation - enemy_transform.translation.xy()).normalize();
+        // normalize the desired direction, nothing to turn towards if it is zero.
+        let Some(to_player) = desired.try_normalize() else {
+            continue;
+        };
 
-            // get the dot product between the enemy forward vector and the direction to the player.
-            let forward_dot_player = enemy_forward.dot(to_player);
+        // get the dot product between the enemy forward vector and the direction to the player.
+        let forward_dot_player = enemy_forward.dot(to_player);
 
-            // if the dot product is approximately 1.0 then the enemy is already facing the player and
-            // we can early out.
-            if (forward_dot_player - 1.0).abs() < f32::EPSILON {
-                continue;
-            }
+        // if the dot product is approximately 1.0 then the enemy is already facing the player and
+        // we can early out.
+        if (forward_dot_player - 1.0).abs() < f32::EPSILON {
+            continue;
+        }
+
+        // get the right vector of the enemy ship in 2D (already unit length)
+        let enemy_right = (enemy_transform.rotation * Vec3::X).xy();
 
-            // get the right vector of the enemy ship in 2D (already unit length)
-            let enemy_right = (enemy_transform.rotation * Vec3::X).xy();
+        // get the dot product of the enemy right vector and the direction to the player ship.
+        // if the dot product is negative them we need to rotate counter clockwise, if it is
+        // positive we need to rotate clockwise. Note that `copysign` will still return 1.0 if the
+        // dot product is 0.0 (because the player is directly behind the enemy, so perpendicular
+        // with the right vector).
+        let right_dot_player = enemy_right.dot(to_player);
 
-            // get the dot product of the enemy right vector and the direction to the player ship.
-            // if the dot product is negative them we need to rotate counter clockwise, if it is
-            // positive we need to rotate clockwise. Note that `copysign` will still return 1.0 if the
-            // dot product is 0.0 (because the player is directly behind the enemy, so perpendicular
-            // with the right vector).
-            let right_dot_player = enemy_right.dot(to_player);
+        // determine the sign of rotation from the right dot player. We need to negate the sign
+        // here as the 2D bevy co-ordinate system rotates around +Z, which is pointing out of the
+        // screen. Due to the right hand rule, positive rotation around +Z is counter clockwise and
+        // negative is clockwise.
+        let rotation_sign = -f32::copysign(1.0, right_dot_player);
 
-            // determine the sign of rotation from the right dot player. We need to negate the sign
-            // here as the 2D bevy co-ordinate system rotates around +Z, which is pointing out of the
-            // screen. Due to the right hand rule, positive rotation around +Z is counter clockwise and
-            // negative is clockwise.
-            let rotation_sign = -f32::copysign(1.0, right_dot_player);
+        // limit rotation so we don't overshoot the target. We need to convert our dot product to
+        // an angle here so we can get an angle of rotation to clamp against.
+        let max_angle = forward_dot_player.clamp(-1.0, 1.0).acos(); // clamp acos for safety
 
-            // limit rotation so we don't overshoot the target. We need to convert our dot product to
-            // an angle here so we can get an angle of rotation to clamp against.
-            let max_angle = forward_dot_player.clamp(-1.0, 1.0).acos(); // clamp acos for safety
+        // calculate angle of rotation with limit
+        let rotation_angle = rotation_sign * (config.speed * time.delta_seconds()).min(max_angle);
 
-            // calculate angle of rotation with limit
-            let rotation_angle =
-                rotation_sign * (config.speed * time.delta_seconds()).min(max_angle);
+        // rotate the enemy to face the player
+        enemy_transform.rotate_z(rotation_angle);
+    }
+}
 
-            // rotate the enemy to face the player
-            enemy_transform.rotate_z(rotation_angle);
+/// Applies each entity's `BoundaryBehavior` once it has left the arena.
+pub fn boundary_system(
+    mut commands: Commands,
+    mut query: Query<(Entity, &BoundaryBehavior, &mut Transform), With<Move>>,
+    arena: Res<Arena>,
+) {
+    let half = arena.bounds / 2.0;
+    for (e, behavior, mut t) in &mut query {
+        let pos = t.translation.xy();
+        let outside = pos.abs().cmpgt(half);
+        if !outside.any() {
+            continue;
+        }
+        match behavior {
+            BoundaryBehavior::Clamp => {
+                t.translation = pos.clamp(-half, half).extend(t.translation.z);
+            }
+            BoundaryBehavior::Wrap => {
+                let wrapped = Vec2::new(
+                    (pos.x + half.x).rem_euclid(arena.bounds.x) - half.x,
+                    (pos.y + half.y).rem_euclid(arena.bounds.y) - half.y,
+                );
+                t.translation = wrapped.extend(t.translation.z);
+            }
+            BoundaryBehavior::Bounce => {
+                // mirror the heading on every axis that crossed the edge
+                let mut heading = t.up().xy();
+                if outside.x && heading.x * pos.x > 0.0 {
+                    heading.x = -heading.x;
+                }
+                if outside.y && heading.y * pos.y > 0.0 {
+                    heading.y = -heading.y;
+                }
+                t.rotation = Quat::from_rotation_arc_2d(Vec2::Y, heading);
+                t.translation = pos.clamp(-half, half).extend(t.translation.z);
+            }
+            BoundaryBehavior::Despawn => prefabs::release(&mut commands, e),
         }
     }
 }
@@ -1,62 +1,145 @@
 use crate::{
     components::*,
+    events::{PlayerDied, ShotFired},
+    instancing::Instancing,
     materials::BulletMaterial,
     meshes::BulletMesh,
-    prefabs,
-    resources::{MouseWorldPos, BOUNDS},
+    prefabs::{self, EntityPools, PoolKind},
+    resources::{MouseWorldPos, PlayerInput, RoundParams, RoundPhase},
 };
 use bevy::prelude::*;
 
-pub fn fire_system(
-    mut commands: Commands,
-    player: Query<&Transform, With<Player>>,
+/// Angle between bullets of a multi-shot.
+const SPREAD: f32 = 0.12;
+
+/// Reads the keyboard and mouse into `PlayerInput`, unless a bot is driving.
+pub fn human_input_system(
     keyboard_input: Res<Input<KeyCode>>,
     ms_input: Res<Input<MouseButton>>,
+    ms_pos: Res<MouseWorldPos>,
+    q_windows: Query<&Window>,
+    mut input: ResMut<PlayerInput>,
+) {
+    let mut movement = Vec2::ZERO;
+    if keyboard_input.pressed(KeyCode::A) {
+        movement += Vec2::NEG_X;
+    }
+    if keyboard_input.pressed(KeyCode::D) {
+        movement += Vec2::X;
+    }
+    if keyboard_input.pressed(KeyCode::S) {
+        movement += Vec2::NEG_Y;
+    }
+    if keyboard_input.pressed(KeyCode::W) {
+        movement += Vec2::Y;
+    }
+    let cursor = q_windows
+        .get_single()
+        .ok()
+        .and_then(|w| w.cursor_position());
+    *input = PlayerInput {
+        movement,
+        aim: cursor.map(|_| ms_pos.0),
+        fire: keyboard_input.just_pressed(KeyCode::Space)
+            | ms_input.just_pressed(MouseButton::Left),
+        pick: [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3]
+            .into_iter()
+            .position(|key| keyboard_input.just_pressed(key)),
+    };
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn fire_system(
+    mut commands: Commands,
+    mut pools: ResMut<EntityPools>,
+    instancing: Res<Instancing>,
+    mut player: Query<(&Transform, &mut Weapon), With<Player>>,
+    input: Res<PlayerInput>,
+    round: Res<RoundParams>,
     bullet_mat: Res<BulletMaterial>,
     bullet_mesh: Res<BulletMesh>,
+    mut shots: EventWriter<ShotFired>,
+    time: Res<Time>,
 ) {
-    if let Ok(p) = player.get_single() {
-        if keyboard_input.just_pressed(KeyCode::Space) | ms_input.just_pressed(MouseButton::Left) {
-            let mut b_transf = p.clone();
-            b_transf.translation += b_transf.up() * 2.0;
+    if let Ok((p, mut weapon)) = player.get_single_mut() {
+        weapon.remains = (weapon.remains - time.delta_seconds()).max(0.0);
+        // the click that picks an upgrade is not a shot
+        let armed = round.phase != RoundPhase::Intermission;
+        if input.fire && armed && weapon.remains <= 0.0 {
+            weapon.remains = weapon.cooldown;
+            for i in 0..weapon.bullets {
+                // fan extra bullets out evenly around the aim
+                let offset = i as f32 - (weapon.bullets - 1) as f32 / 2.0;
+                let mut b_transf = *p;
+                b_transf.rotate_z(offset * SPREAD);
+                b_transf.translation += b_transf.up() * 2.0;
 
-            commands.spawn((
-                prefabs::bullet_bundle(&bullet_mesh, &bullet_mat, b_transf),
-                Bullet,
-                Move { speed: 1000.0 },
-                Decay {
-                    max_seconds: 0.5,
-                    elapsed_time: 0.0,
-                },
-                Sensor { radius: 3.0 },
-            ));
+                let mut bullet = pools.acquire(&mut commands, PoolKind::Bullet);
+                prefabs::insert_visuals(
+                    &mut bullet,
+                    &instancing,
+                    &bullet_mesh.0,
+                    bullet_mat.0.clone(),
+                    b_transf,
+                );
+                bullet.insert((
+                    Bullet,
+                    Pierce(weapon.pierce),
+                    Move {
+                        speed: weapon.bullet_speed,
+                    },
+                    BoundaryBehavior::Despawn,
+                    Decay {
+                        max_seconds: weapon.bullet_lifetime,
+                        elapsed_time: 0.0,
+                    },
+                    Sensor { radius: 3.0 },
+                ));
+                shots.send(ShotFired {
+                    position: b_transf.translation.truncate(),
+                    direction: b_transf.up().truncate(),
+                });
+            }
         }
     }
 }
 
+/// Rewrites the player's components from their stats whenever a modifier is added.
+pub fn apply_stats_system(
+    mut query: Query<
+        (
+            &PlayerStats,
+            &mut Move,
+            &mut Health,
+            &mut MaxHealth,
+            &mut Weapon,
+            &mut HitCooldown,
+        ),
+        Changed<PlayerStats>,
+    >,
+) {
+    for (stats, mut mv, mut health, mut max, mut weapon, mut hit) in &mut query {
+        mv.speed = stats.get(Stat::MoveSpeed);
+        // raising max health heals by the difference, lowering it never kills
+        let max_health = stats.get(Stat::MaxHealth).round();
+        health.0 = (health.0 + max_health - max.0).clamp(1.0, max_health);
+        max.0 = max_health;
+        weapon.cooldown = 1.0 / stats.get(Stat::FireRate);
+        weapon.bullets = stats.get(Stat::BulletCount).round() as u32;
+        weapon.pierce = stats.get(Stat::BulletPierce).round() as u32;
+        weapon.bullet_speed = stats.get(Stat::BulletSpeed);
+        weapon.bullet_lifetime = stats.get(Stat::BulletLifetime);
+        hit.time_full = stats.get(Stat::HitCooldown);
+    }
+}
+
 pub fn movement_system(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut query: Query<(&Player, &mut Transform, &Move)>,
+    input: Res<PlayerInput>,
+    mut query: Query<(&Player, &mut Transform, &Move, Option<&mut Velocity>)>,
     time: Res<Time>,
 ) {
-    if let Ok((_, mut transform, mv)) = query.get_single_mut() {
-        let mut movement_vector = Vec2::ZERO;
-
-        if keyboard_input.pressed(KeyCode::A) {
-            movement_vector += Vec2::NEG_X;
-        }
-
-        if keyboard_input.pressed(KeyCode::D) {
-            movement_vector += Vec2::X;
-        }
-
-        if keyboard_input.pressed(KeyCode::S) {
-            movement_vector += Vec2::NEG_Y;
-        }
-
-        if keyboard_input.pressed(KeyCode::W) {
-            movement_vector += Vec2::Y;
-        }
+    if let Ok((_, mut transform, mv, velocity)) = query.get_single_mut() {
+        let movement_vector = input.movement;
 
         // update the ship rotation around the Z axis (perpendicular to the 2D plane of the screen)
         // transform.rotate_z(rotation_factor * rot.speed * time.delta_seconds());
@@ -68,20 +151,17 @@ pub fn movement_system(
         // update the ship translation with our new translation delta
         transform.translation += Vec3::new(mov.x, mov.y, 0.0);
 
-        // bound the ship within the invisible level bounds
-        let extents = Vec3::from((BOUNDS / 2.0, 0.0));
-        transform.translation = transform.translation.min(extents).max(-extents);
+        // remember the velocity so enemies can lead their pursuit
+        if let Some(mut v) = velocity {
+            v.0 = movement_vector.normalize_or_zero() * mv.speed;
+        }
     }
 }
 
-pub fn aim_system(
-    ms_pos: Res<MouseWorldPos>,
-    q_windows: Query<&Window>,
-    mut query: Query<&mut Transform, With<Player>>,
-) {
-    if let Some(_) = q_windows.single().cursor_position() {
+pub fn aim_system(input: Res<PlayerInput>, mut query: Query<&mut Transform, With<Player>>) {
+    if let Some(aim) = input.aim {
         if let Ok(mut transf) = query.get_single_mut() {
-            let displacement = ms_pos.0 - transf.translation.truncate();
+            let displacement = aim - transf.translation.truncate();
             if let Some(dir) = displacement.try_normalize() {
                 transf.rotation = Quat::from_rotation_arc_2d(Vec2::Y, dir);
             }
@@ -91,11 +171,15 @@ pub fn aim_system(
 
 pub fn died_system(
     mut commands: Commands,
-    player: Query<(Entity, &Health, &Player), Without<Dead>>,
+    player: Query<(Entity, &Transform, &Health, &Player), Without<Dead>>,
+    mut died: EventWriter<PlayerDied>,
 ) {
-    if let Ok((e, h, _)) = player.get_single() {
+    if let Ok((e, t, h, _)) = player.get_single() {
         if h.0 <= 0.0 {
             info!("PLAYER DIED!");
+            died.send(PlayerDied {
+                position: t.translation.truncate(),
+            });
             commands
                 .entity(e)
                 .insert((
@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::*,
+    events::{RoundEnded, RoundStarted},
+    resources::{Arena, GameRng, RoundConfig, RoundParams, RoundPhase, Score, SpawnSettings},
+    systems::spawning,
+};
+
+pub const WARM_UP_SECONDS: f32 = 2.0;
+pub const CLEARED_SECONDS: f32 = 2.0;
+/// Bonus for clearing a round instantly, scaled by the round number and the time left.
+const CLEAR_BONUS: f32 = 100.0;
+
+/// Moves on to the warm-up of the next, larger round.
+pub fn next_round(round: &mut RoundParams, config: &RoundConfig) {
+    round.round += 1;
+    round.enemies = (round.enemies as f32 * config.enemy_growth).ceil() as u32;
+    round.phase = RoundPhase::WarmUp;
+    round.countdown = WARM_UP_SECONDS;
+}
+
+/// Drives the round through warm-up, active, cleared and intermission.
+#[allow(clippy::too_many_arguments)]
+pub fn round_system(
+    mut commands: Commands,
+    mut round: ResMut<RoundParams>,
+    mut score: ResMut<Score>,
+    mut rng: ResMut<GameRng>,
+    arena: Res<Arena>,
+    settings: Res<SpawnSettings>,
+    players: Query<&Transform, With<Player>>,
+    hostiles: Query<(), Or<(With<Enemy>, With<SpawnTelegraph>)>>,
+    mut started: EventWriter<RoundStarted>,
+    mut ended: EventWriter<RoundEnded>,
+    time: Res<Time>,
+) {
+    round.countdown -= time.delta_seconds();
+    match round.phase {
+        RoundPhase::WarmUp => {
+            if round.countdown > 0.0 {
+                return;
+            }
+            let players: Vec<Vec2> = players.iter().map(|t| t.translation.truncate()).collect();
+            for _ in 0..round.enemies {
+                let pos = spawning::random_spawn_point(&mut rng.0, &arena, &settings, &players);
+                let archetype = spawning::random_archetype(&mut rng.0);
+                spawning::spawn_telegraph(&mut commands, &mut rng.0, pos, archetype, &settings);
+            }
+            round.phase = RoundPhase::Active;
+            round.countdown = round.length;
+            started.send(RoundStarted { round: round.round });
+        }
+        RoundPhase::Active => {
+            let cleared = hostiles.is_empty();
+            if !cleared && round.countdown > 0.0 {
+                return;
+            }
+            let bonus = match cleared {
+                true => {
+                    let left = (round.countdown / round.length).clamp(0.0, 1.0);
+                    (left * CLEAR_BONUS * round.round as f32).ceil() as u32
+                }
+                false => 0,
+            };
+            score.0 += bonus;
+            round.cleared = cleared;
+            round.bonus = bonus;
+            round.phase = RoundPhase::Cleared;
+            round.countdown = CLEARED_SECONDS;
+            ended.send(RoundEnded {
+                round: round.round,
+                cleared,
+                bonus,
+            });
+        }
+        RoundPhase::Cleared => {
+            if round.countdown <= 0.0 {
+                round.phase = RoundPhase::Intermission;
+            }
+        }
+        // left by picking an upgrade
+        RoundPhase::Intermission => {}
+    }
+}
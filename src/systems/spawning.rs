@@ -0,0 +1,145 @@
+use crate::{
+    components::*,
+    instancing::Instancing,
+    materials::EnemyMaterial,
+    meshes::EnemyMesh,
+    prefabs::{self, EntityPools},
+    resources::{ArchetypeConfig, Arena, SpawnSettings, SpawnZone},
+};
+use bevy::{math::Vec3Swizzles, prelude::*};
+use rand::Rng;
+
+/// Candidates tried before settling for the one farthest from the players.
+const SPAWN_ATTEMPTS: u32 = 16;
+
+pub fn random_archetype(rng: &mut impl Rng) -> EnemyArchetype {
+    match rng.gen::<f32>() {
+        r if r < 0.6 => EnemyArchetype::Grunt,
+        r if r < 0.85 => EnemyArchetype::Hunter,
+        _ => EnemyArchetype::Swarmer,
+    }
+}
+
+type MinMax = (f32, f32);
+pub fn random_2d(rng: &mut impl Rng, x_range: MinMax, y_range: MinMax) -> Vec2 {
+    let x: f32 = rng.gen_range(x_range.0..x_range.1);
+    let y: f32 = rng.gen_range(y_range.0..y_range.1);
+    Vec2::new(x, y)
+}
+
+fn random_in_zone(rng: &mut impl Rng, zone: SpawnZone) -> Vec2 {
+    let min = zone.center - zone.half_size;
+    let max = zone.center + zone.half_size;
+    random_2d(rng, (min.x, max.x), (min.y, max.y))
+}
+
+/// Random point along one of the four arena edges.
+fn random_on_edge(rng: &mut impl Rng, arena: &Arena, inset: f32) -> Vec2 {
+    let half = arena.bounds / 2.0 - Vec2::splat(inset);
+    let along = rng.gen_range(-1.0..1.0);
+    match rng.gen_range(0..4) {
+        0 => Vec2::new(-half.x, along * half.y),
+        1 => Vec2::new(half.x, along * half.y),
+        2 => Vec2::new(along * half.x, -half.y),
+        _ => Vec2::new(along * half.x, half.y),
+    }
+}
+
+fn random_candidate(rng: &mut impl Rng, arena: &Arena, settings: &SpawnSettings) -> Vec2 {
+    if rng.gen::<f32>() < settings.edge_chance {
+        return random_on_edge(rng, arena, settings.edge_inset);
+    }
+    let zone = match arena.spawn_zones.is_empty() {
+        true => SpawnZone {
+            center: Vec2::ZERO,
+            half_size: arena.bounds / 2.0,
+        },
+        false => arena.spawn_zones[rng.gen_range(0..arena.spawn_zones.len())],
+    };
+    random_in_zone(rng, zone)
+}
+
+/// Random spawn point from the zones or the arena edge, kept away from every player.
+pub fn random_spawn_point(
+    rng: &mut impl Rng,
+    arena: &Arena,
+    settings: &SpawnSettings,
+    players: &[Vec2],
+) -> Vec2 {
+    let clearance = |p: Vec2| {
+        players
+            .iter()
+            .map(|player| player.distance(p))
+            .fold(f32::INFINITY, f32::min)
+    };
+    let mut best = (Vec2::ZERO, f32::NEG_INFINITY);
+    for _ in 0..SPAWN_ATTEMPTS {
+        let candidate = random_candidate(rng, arena, settings);
+        let distance = clearance(candidate);
+        if distance >= settings.min_player_distance {
+            return candidate;
+        }
+        if distance > best.1 {
+            best = (candidate, distance);
+        }
+    }
+    best.0
+}
+
+/// Places a telegraph where an enemy will materialize.
+pub fn spawn_telegraph(
+    commands: &mut Commands,
+    rng: &mut impl Rng,
+    position: Vec2,
+    archetype: EnemyArchetype,
+    settings: &SpawnSettings,
+) {
+    let mut t = Transform::from_translation(position.extend(0.0));
+    t.rotate_z(rng.gen::<f32>() * 360.0);
+    commands.spawn((
+        TransformBundle::from_transform(t),
+        SpawnTelegraph {
+            archetype,
+            duration: settings.telegraph_seconds,
+            elapsed: 0.0,
+        },
+    ));
+}
+
+/// Grows a ring on each telegraph and swaps it for the enemy once it is done.
+#[allow(clippy::too_many_arguments)]
+pub fn telegraph_system(
+    mut commands: Commands,
+    mut pools: ResMut<EntityPools>,
+    instancing: Res<Instancing>,
+    mut gizmos: Gizmos,
+    mut query: Query<(Entity, &Transform, &mut SpawnTelegraph)>,
+    enemy_mesh: Res<EnemyMesh>,
+    enemy_mat: Res<EnemyMaterial>,
+    archetypes: Res<ArchetypeConfig>,
+    time: Res<Time>,
+) {
+    for (e, t, mut telegraph) in &mut query {
+        telegraph.elapsed += time.delta_seconds();
+        let progress = (telegraph.elapsed / telegraph.duration).min(1.0);
+        if progress >= 1.0 {
+            commands.entity(e).despawn_recursive();
+            let Some(stats) = archetypes.0.get(&telegraph.archetype) else {
+                continue;
+            };
+            prefabs::spawn_enemy(
+                &mut commands,
+                &mut pools,
+                &instancing,
+                &enemy_mesh,
+                &enemy_mat,
+                *t,
+                telegraph.archetype,
+                *stats,
+            );
+            continue;
+        }
+        let color = Color::rgba(1.0, 0.2, 0.2, 0.3 + 0.7 * progress);
+        gizmos.circle_2d(t.translation.xy(), 2.0 + 12.0 * progress, color);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::{
+    components::*,
+    navigation::{FlowField, NavGrid},
+    resources::{GameRng, SteeringConfig},
+};
+use bevy::{math::Vec3Swizzles, prelude::*, utils::HashMap};
+use rand::Rng;
+
+type Neighbour = (Vec2, Vec2);
+
+pub fn seek(pos: Vec2, target: Vec2) -> Vec2 {
+    (target - pos).normalize_or_zero()
+}
+
+pub fn flee(pos: Vec2, threat: Vec2) -> Vec2 {
+    -seek(pos, threat)
+}
+
+/// Like `seek`, but fades out inside `slowing_radius` so the agent comes to rest on the target.
+pub fn arrive(pos: Vec2, target: Vec2, slowing_radius: f32) -> Vec2 {
+    let to_target = target - pos;
+    let ramp = (to_target.length() / slowing_radius).min(1.0);
+    to_target.normalize_or_zero() * ramp
+}
+
+/// Seeks where the target will be, looking ahead by the time needed to reach it.
+pub fn pursue(pos: Vec2, target: Vec2, target_vel: Vec2, speed: f32, max_prediction: f32) -> Vec2 {
+    let prediction = match speed > 0.0 {
+        true => (pos.distance(target) / speed).min(max_prediction),
+        false => max_prediction,
+    };
+    seek(pos, target + target_vel * prediction)
+}
+
+/// Random walk of a point on a circle ahead of the agent.
+pub fn wander(rng: &mut impl Rng, heading: Vec2, wander_angle: &mut f32, jitter: f32) -> Vec2 {
+    *wander_angle += (rng.gen::<f32>() * 2.0 - 1.0) * jitter;
+    (heading * 2.0 + Vec2::from_angle(*wander_angle)).normalize_or_zero()
+}
+
+/// Pushes away from neighbours closer than `radius`, harder the closer they are.
+pub fn separation(pos: Vec2, neighbours: &[Neighbour], radius: f32) -> Vec2 {
+    let mut push = Vec2::ZERO;
+    for (n_pos, _) in neighbours {
+        let away = pos - *n_pos;
+        let dist = away.length();
+        if dist > 0.0 && dist < radius {
+            push += away / (dist * dist);
+        }
+    }
+    push.normalize_or_zero()
+}
+
+pub fn alignment(neighbours: &[Neighbour]) -> Vec2 {
+    neighbours
+        .iter()
+        .fold(Vec2::ZERO, |acc, (_, vel)| acc + *vel)
+        .normalize_or_zero()
+}
+
+pub fn cohesion(pos: Vec2, neighbours: &[Neighbour]) -> Vec2 {
+    if neighbours.is_empty() {
+        return Vec2::ZERO;
+    }
+    let center = neighbours.iter().fold(Vec2::ZERO, |acc, (p, _)| acc + *p);
+    seek(pos, center / neighbours.len() as f32)
+}
+
+fn cell(pos: Vec2, cell_size: f32) -> IVec2 {
+    (pos / cell_size).floor().as_ivec2()
+}
+
+/// Blends every behavior of an enemy's archetype profile into `Steering::desired`.
+/// Neighbours are looked up through a spatial hash rebuilt every frame.
+#[allow(clippy::too_many_arguments)]
+pub fn steering_system(
+    config: Res<SteeringConfig>,
+    nav_grid: Res<NavGrid>,
+    flow: Res<FlowField>,
+    mut query: Query<(
+        Entity,
+        &EnemyArchetype,
+        &Transform,
+        &Move,
+        &Target,
+        Option<&PathFollow>,
+        &mut Steering,
+    )>,
+    agents: Query<(Entity, &Transform, &Velocity), With<Steering>>,
+    targets: Query<(&Transform, Option<&Velocity>), With<Targetable>>,
+    mut grid: Local<HashMap<IVec2, Vec<(Entity, Vec2, Vec2)>>>,
+    mut neighbours: Local<Vec<Neighbour>>,
+    mut rng: ResMut<GameRng>,
+    time: Res<Time>,
+) {
+    let cell_size = config
+        .0
+        .values()
+        .map(|p| p.neighbour_radius)
+        .fold(1.0, f32::max);
+
+    grid.values_mut().for_each(Vec::clear);
+    for (e, t, v) in &agents {
+        let pos = t.translation.xy();
+        grid.entry(cell(pos, cell_size))
+            .or_default()
+            .push((e, pos, v.0));
+    }
+
+    for (e, archetype, t, mv, target, path, mut steering) in &mut query {
+        let Some(profile) = config.0.get(archetype) else {
+            continue;
+        };
+        let pos = t.translation.xy();
+
+        neighbours.clear();
+        let center = cell(pos, cell_size);
+        for x in -1..=1 {
+            for y in -1..=1 {
+                let Some(cell_agents) = grid.get(&(center + IVec2::new(x, y))) else {
+                    continue;
+                };
+                for (n_e, n_pos, n_vel) in cell_agents {
+                    if *n_e != e && pos.distance(*n_pos) < profile.neighbour_radius {
+                        neighbours.push((*n_pos, *n_vel));
+                    }
+                }
+            }
+        }
+
+        let mut desired = Vec2::ZERO;
+        // the target is behind an obstacle, follow the flow field or the next waypoint instead
+        let detour = match target.0.is_some() && target.0 == flow.goal {
+            true => flow.sample(&nav_grid, pos),
+            false => path
+                .and_then(|p| p.waypoints.first())
+                .map(|waypoint| seek(pos, *waypoint)),
+        };
+        if let Some(dir) = detour {
+            let weight = profile.seek + profile.arrive + profile.pursue;
+            desired += weight * dir;
+        } else if let Some((t_t, t_v)) = target.0.and_then(|e| targets.get(e).ok()) {
+            let t_pos = t_t.translation.xy();
+            let t_vel = t_v.map_or(Vec2::ZERO, |v| v.0);
+            desired += profile.seek * seek(pos, t_pos);
+            desired += profile.arrive * arrive(pos, t_pos, profile.slowing_radius);
+            desired += profile.pursue * pursue(pos, t_pos, t_vel, mv.speed, profile.max_prediction);
+            if pos.distance(t_pos) < profile.flee_radius {
+                desired += profile.flee * flee(pos, t_pos);
+            }
+        }
+        let jitter = profile.wander_jitter * time.delta_seconds();
+        let heading = t.up().xy();
+        desired += profile.wander * wander(&mut rng.0, heading, &mut steering.wander_angle, jitter);
+        desired += profile.separation * separation(pos, &neighbours, profile.separation_radius);
+        desired += profile.alignment * alignment(&neighbours);
+        desired += profile.cohesion * cohesion(pos, &neighbours);
+
+        steering.desired = desired.clamp_length_max(1.0);
+    }
+}
@@ -0,0 +1,29 @@
+use crate::components::*;
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+/// Keeps every `Target` pointing at a live, non-`Dead` `Targetable`.
+/// A valid target is kept; otherwise the best one is picked by distance weighted by threat.
+pub fn acquire_target_system(
+    mut seekers: Query<(&Transform, &mut Target), Without<Targetable>>,
+    targets: Query<(Entity, &Transform, &Targetable), Without<Dead>>,
+) {
+    for (s_t, mut target) in &mut seekers {
+        if let Some(current) = target.0 {
+            if targets.contains(current) {
+                continue;
+            }
+        }
+
+        let s_pos = s_t.translation.xy();
+        let best = targets
+            .iter()
+            .filter(|(_, _, t)| t.threat > 0.0)
+            .map(|(e, t_t, t)| (e, s_pos.distance(t_t.translation.xy()) / t.threat))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(e, _)| e);
+
+        if target.0 != best {
+            target.0 = best;
+        }
+    }
+}
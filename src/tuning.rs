@@ -0,0 +1,139 @@
+use bevy::{input::common_conditions::input_just_pressed, prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::*,
+    resources::{
+        ArchetypeConfig, ArchetypeStats, Arena, MouseWorldPos, RoundConfig, RoundParams,
+        SpawnSettings, SpawnZone, SteeringConfig, SteeringProfile,
+    },
+};
+
+/// Balancing values, loaded on startup and written back by the export.
+pub const TUNING_PATH: &str = "assets/tuning.ron";
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Tuning {
+    archetypes: HashMap<EnemyArchetype, ArchetypeStats>,
+    steering: HashMap<EnemyArchetype, SteeringProfile>,
+    rounds: RoundConfig,
+    spawn: SpawnSettings,
+}
+
+fn load_tuning(mut commands: Commands) {
+    let Ok(text) = std::fs::read_to_string(TUNING_PATH) else {
+        return;
+    };
+    let tuning: Tuning = match ron::de::from_str(&text) {
+        Ok(tuning) => tuning,
+        Err(e) => {
+            error!("failed to load {TUNING_PATH}: {e}");
+            return;
+        }
+    };
+    // archetypes missing from the file keep their defaults
+    let mut archetypes = ArchetypeConfig::default();
+    archetypes.0.extend(tuning.archetypes);
+    let mut steering = SteeringConfig::default();
+    steering.0.extend(tuning.steering);
+    commands.insert_resource(archetypes);
+    commands.insert_resource(steering);
+    commands.insert_resource(tuning.rounds);
+    commands.insert_resource(tuning.spawn);
+}
+
+/// Pulls values tweaked live in the inspector into the configs and writes them to the tuning
+/// file. An enemy edited since the last export speaks for its archetype, the others keep theirs.
+fn export_tuning(
+    mut archetypes: ResMut<ArchetypeConfig>,
+    mut rounds: ResMut<RoundConfig>,
+    steering: Res<SteeringConfig>,
+    spawn: Res<SpawnSettings>,
+    round: Option<Res<RoundParams>>,
+    enemies: Query<
+        (&EnemyArchetype, &Move, &RotateToPlayer, &Sensor),
+        (
+            With<Enemy>,
+            Or<(Changed<Move>, Changed<RotateToPlayer>, Changed<Sensor>)>,
+        ),
+    >,
+) {
+    for (archetype, mv, rotate, sensor) in &enemies {
+        let stats = ArchetypeStats {
+            speed: mv.speed,
+            turn_speed: rotate.speed,
+            radius: sensor.radius,
+        };
+        // freshly spawned enemies count as changed too, they still match their archetype
+        if archetypes.0.get(archetype) != Some(&stats) {
+            archetypes.0.insert(*archetype, stats);
+        }
+    }
+    if let Some(round) = round {
+        rounds.length = round.length;
+    }
+    let tuning = Tuning {
+        archetypes: archetypes.0.clone(),
+        steering: steering.0.clone(),
+        rounds: rounds.clone(),
+        spawn: spawn.clone(),
+    };
+    let tmp = format!("{TUNING_PATH}.tmp");
+    let saved = ron::ser::to_string_pretty(&tuning, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())
+        .and_then(|s| std::fs::write(&tmp, s).map_err(|e| e.to_string()))
+        .and_then(|_| std::fs::rename(&tmp, TUNING_PATH).map_err(|e| e.to_string()));
+    match saved {
+        Ok(()) => info!("exported tuning to {TUNING_PATH}"),
+        Err(e) => error!("failed to export {TUNING_PATH}: {e}"),
+    }
+}
+
+/// Keeps live enemies in line with their archetype when the config is edited.
+fn apply_archetype_config(
+    config: Res<ArchetypeConfig>,
+    mut enemies: Query<(&EnemyArchetype, &mut Move, &mut RotateToPlayer, &mut Sensor), With<Enemy>>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+    for (archetype, mut mv, mut rotate, mut sensor) in &mut enemies {
+        let Some(stats) = config.0.get(archetype) else {
+            continue;
+        };
+        mv.speed = stats.speed;
+        rotate.speed = stats.turn_speed;
+        sensor.radius = stats.radius;
+    }
+}
+
+pub struct TuningPlugin;
+
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ArchetypeConfig>()
+            .init_resource::<RoundConfig>()
+            .register_type::<Arena>()
+            .register_type::<SpawnZone>()
+            .register_type::<Vec<SpawnZone>>()
+            .register_type::<MouseWorldPos>()
+            .register_type::<ArchetypeStats>()
+            .register_type::<HashMap<EnemyArchetype, ArchetypeStats>>()
+            .register_type::<ArchetypeConfig>()
+            .register_type::<RoundConfig>()
+            .register_type::<SteeringProfile>()
+            .register_type::<HashMap<EnemyArchetype, SteeringProfile>>()
+            .register_type::<SteeringConfig>()
+            .register_type::<SpawnSettings>()
+            .add_systems(Startup, load_tuning)
+            .add_systems(
+                Update,
+                (
+                    export_tuning.run_if(input_just_pressed(KeyCode::F6)),
+                    apply_archetype_config,
+                )
+                    .chain(),
+            );
+    }
+}
@@ -1,4 +1,12 @@
-use crate::states::AppState;
+use crate::{
+    components::Stat,
+    events::{ButtonHovered, ButtonPressed},
+    profile::{currency_for, ItemStatus, Profile, ShopItem},
+    resources::{RoundParams, Score},
+    save::{self, ResumeRun},
+    states::AppState,
+    upgrades::RunSummary,
+};
 use bevy::prelude::*;
 
 #[derive(Resource)]
@@ -11,16 +19,59 @@ struct GameOverData {
     button_entity: Entity,
 }
 
-const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
-const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
-const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
+pub const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+pub const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
+pub const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
+
+/// What a menu button does when pressed.
+#[derive(Component, Clone, Copy)]
+enum MenuButton {
+    Play,
+    /// Picks the saved run back up.
+    Continue,
+    Editor,
+    Shop,
+    Menu,
+}
+
+fn spawn_menu_button(parent: &mut ChildBuilder, label: &str, action: MenuButton) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(200.),
+                    height: Val::Px(65.),
+                    margin: UiRect::all(Val::Px(5.)),
+                    // horizontally center child text
+                    justify_content: JustifyContent::Center,
+                    // vertically center child text
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: NORMAL_BUTTON.into(),
+                ..default()
+            },
+            action,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+        });
+}
 
 fn setup_menu(mut commands: Commands) {
     let button_entity = commands
         .spawn(NodeBundle {
             style: Style {
-                // center button
+                // center buttons
                 width: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
                 ..default()
@@ -28,50 +79,45 @@ fn setup_menu(mut commands: Commands) {
             ..default()
         })
         .with_children(|parent| {
-            parent
-                .spawn(ButtonBundle {
-                    style: Style {
-                        width: Val::Px(150.),
-                        height: Val::Px(65.),
-                        // horizontally center child text
-                        justify_content: JustifyContent::Center,
-                        // vertically center child text
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    background_color: NORMAL_BUTTON.into(),
-                    ..default()
-                })
-                .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        "Play",
-                        TextStyle {
-                            font_size: 40.0,
-                            color: Color::rgb(0.9, 0.9, 0.9),
-                            ..default()
-                        },
-                    ));
-                });
+            if save::has_save() {
+                spawn_menu_button(parent, "Continue", MenuButton::Continue);
+            }
+            spawn_menu_button(parent, "Play", MenuButton::Play);
+            spawn_menu_button(parent, "Shop", MenuButton::Shop);
+            spawn_menu_button(parent, "Editor", MenuButton::Editor);
         })
         .id();
     commands.insert_resource(MenuData { button_entity });
 }
 
 fn menu(
+    mut commands: Commands,
     mut next_state: ResMut<NextState<AppState>>,
+    mut hovered: EventWriter<ButtonHovered>,
+    mut pressed: EventWriter<ButtonPressed>,
     mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor),
+        (&Interaction, &MenuButton, &mut BackgroundColor),
         (Changed<Interaction>, With<Button>),
     >,
 ) {
-    for (interaction, mut color) in &mut interaction_query {
+    for (interaction, action, mut color) in &mut interaction_query {
         match *interaction {
             Interaction::Pressed => {
                 *color = PRESSED_BUTTON.into();
-                next_state.set(AppState::InGame);
+                pressed.send(ButtonPressed);
+                if let MenuButton::Continue = action {
+                    commands.insert_resource(ResumeRun);
+                }
+                next_state.set(match action {
+                    MenuButton::Play | MenuButton::Continue => AppState::InGame,
+                    MenuButton::Editor => AppState::Editor,
+                    MenuButton::Shop => AppState::Shop,
+                    MenuButton::Menu => AppState::Menu,
+                });
             }
             Interaction::Hovered => {
                 *color = HOVERED_BUTTON.into();
+                hovered.send(ButtonHovered);
             }
             Interaction::None => {
                 *color = NORMAL_BUTTON.into();
@@ -84,8 +130,38 @@ fn cleanup_menu(mut commands: Commands, menu_data: Res<MenuData>) {
     commands.entity(menu_data.button_entity).despawn_recursive();
 }
 
-fn setup_game_over(mut commands: Commands, asset_server: Res<AssetServer>) {
+/// Round reached, score, upgrades taken and the stats they added up to.
+fn run_summary(round: Option<&RoundParams>, score: &Score, summary: &RunSummary) -> String {
+    let stats = &summary.stats;
+    let picks = match summary.picks.is_empty() {
+        true => "none".to_string(),
+        false => summary.picks.join(", "),
+    };
+    format!(
+        "round {}   score {}   +{} coins\nupgrades: {}\n\
+         speed {:.0}  health {:.0}  fire rate {:.1}/s  bullets {:.0}  pierce {:.0}  shield {:.1}s",
+        round.map_or(0, |r| r.round),
+        score.0,
+        currency_for(score.0),
+        picks,
+        stats.get(Stat::MoveSpeed),
+        stats.get(Stat::MaxHealth),
+        stats.get(Stat::FireRate),
+        stats.get(Stat::BulletCount),
+        stats.get(Stat::BulletPierce),
+        stats.get(Stat::HitCooldown),
+    )
+}
+
+fn setup_game_over(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    round: Option<Res<RoundParams>>,
+    score: Res<Score>,
+    summary: Res<RunSummary>,
+) {
     let font = asset_server.load("fonts/Kenney Mini.ttf");
+    let summary = run_summary(round.as_deref(), &score, &summary);
     let button_entity = commands
         .spawn(NodeBundle {
             style: Style {
@@ -115,50 +191,157 @@ fn setup_game_over(mut commands: Commands, asset_server: Res<AssetServer>) {
                     ..default()
                 }),
             );
+            parent.spawn(
+                TextBundle::from_section(
+                    summary,
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 20.0,
+                        color: Color::rgb(0.8, 0.8, 0.8),
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center)
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(20.)),
+                    ..default()
+                }),
+            );
+            spawn_menu_button(parent, "Restart", MenuButton::Play);
+            spawn_menu_button(parent, "Menu", MenuButton::Menu);
+        })
+        .id();
+    commands.insert_resource(GameOverData { button_entity });
+}
+
+fn cleanup_game_over(mut commands: Commands, game_over_data: Res<GameOverData>) {
+    commands
+        .entity(game_over_data.button_entity)
+        .despawn_recursive();
+}
+
+#[derive(Resource)]
+struct ShopData {
+    root: Entity,
+}
+
+#[derive(Component, Clone, Copy)]
+struct ShopButton(ShopItem);
+
+fn spawn_shop(commands: &mut Commands, font: Handle<Font>, profile: &Profile) -> Entity {
+    let text = |value: String, size: f32| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font.clone(),
+                font_size: size,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        )
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(text("SHOP".to_string(), 60.0));
+            parent.spawn(text(
+                format!(
+                    "{} coins   best score {}   runs {}",
+                    profile.currency, profile.best_score, profile.runs
+                ),
+                24.0,
+            ));
             parent
-                .spawn(ButtonBundle {
+                .spawn(NodeBundle {
                     style: Style {
-                        width: Val::Px(200.),
-                        height: Val::Px(65.),
-                        // horizontally center child text
+                        flex_wrap: FlexWrap::Wrap,
                         justify_content: JustifyContent::Center,
-                        // vertically center child text
-                        align_items: AlignItems::Center,
+                        max_width: Val::Px(800.),
+                        margin: UiRect::all(Val::Px(10.)),
                         ..default()
                     },
-                    background_color: NORMAL_BUTTON.into(),
                     ..default()
                 })
                 .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        "Restart",
-                        TextStyle {
-                            font_size: 40.0,
-                            color: Color::rgb(0.9, 0.9, 0.9),
-                            ..default()
-                        },
-                    ));
+                    for item in ShopItem::ALL {
+                        let status = match item.status(profile) {
+                            ItemStatus::Selected => "equipped".to_string(),
+                            ItemStatus::Owned => "owned".to_string(),
+                            ItemStatus::Buy(cost) => format!("{cost} coins"),
+                            ItemStatus::Maxed => "maxed".to_string(),
+                        };
+                        let label = match item {
+                            ShopItem::Perk(p) => {
+                                format!("{} {}\n{status}", item.label(), profile.perk_level(p))
+                            }
+                            _ => format!("{}\n{status}", item.label()),
+                        };
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        width: Val::Px(180.),
+                                        height: Val::Px(60.),
+                                        margin: UiRect::all(Val::Px(5.)),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    background_color: NORMAL_BUTTON.into(),
+                                    ..default()
+                                },
+                                ShopButton(item),
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(
+                                    text(label, 18.0).with_text_alignment(TextAlignment::Center),
+                                );
+                            });
+                    }
                 });
+            spawn_menu_button(parent, "Back", MenuButton::Menu);
         })
-        .id();
-    commands.insert_resource(GameOverData { button_entity });
+        .id()
 }
 
-fn game_over(
-    mut next_state: ResMut<NextState<AppState>>,
+fn setup_shop(mut commands: Commands, asset_server: Res<AssetServer>, profile: Res<Profile>) {
+    let root = spawn_shop(
+        &mut commands,
+        asset_server.load("fonts/Kenney Mini.ttf"),
+        &profile,
+    );
+    commands.insert_resource(ShopData { root });
+}
+
+/// Buys or equips the clicked item and saves the profile right away.
+fn shop(
+    mut profile: ResMut<Profile>,
+    mut hovered: EventWriter<ButtonHovered>,
+    mut pressed: EventWriter<ButtonPressed>,
     mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor),
-        (Changed<Interaction>, With<Button>),
+        (&Interaction, &ShopButton, &mut BackgroundColor),
+        Changed<Interaction>,
     >,
 ) {
-    for (interaction, mut color) in &mut interaction_query {
+    for (interaction, button, mut color) in &mut interaction_query {
         match *interaction {
             Interaction::Pressed => {
                 *color = PRESSED_BUTTON.into();
-                next_state.set(AppState::InGame);
+                pressed.send(ButtonPressed);
+                if profile.activate(button.0) {
+                    profile.save();
+                }
             }
             Interaction::Hovered => {
                 *color = HOVERED_BUTTON.into();
+                hovered.send(ButtonHovered);
             }
             Interaction::None => {
                 *color = NORMAL_BUTTON.into();
@@ -167,10 +350,26 @@ fn game_over(
     }
 }
 
-fn cleanup_game_over(mut commands: Commands, game_over_data: Res<GameOverData>) {
-    commands
-        .entity(game_over_data.button_entity)
-        .despawn_recursive();
+/// Rebuilds the shop so prices and ownership follow the profile.
+fn refresh_shop(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    profile: Res<Profile>,
+    mut shop_data: ResMut<ShopData>,
+) {
+    if !profile.is_changed() {
+        return;
+    }
+    commands.entity(shop_data.root).despawn_recursive();
+    shop_data.root = spawn_shop(
+        &mut commands,
+        asset_server.load("fonts/Kenney Mini.ttf"),
+        &profile,
+    );
+}
+
+fn cleanup_shop(mut commands: Commands, shop_data: Res<ShopData>) {
+    commands.entity(shop_data.root).despawn_recursive();
 }
 
 pub struct UIPlugin;
@@ -180,11 +379,26 @@ impl Plugin for UIPlugin {
         app
             // Menu
             .add_systems(OnEnter(AppState::Menu), setup_menu)
-            .add_systems(Update, menu.run_if(in_state(AppState::Menu)))
+            .add_systems(
+                Update,
+                menu.run_if(
+                    in_state(AppState::Menu)
+                        .or_else(in_state(AppState::GameOver))
+                        .or_else(in_state(AppState::Shop)),
+                ),
+            )
             .add_systems(OnExit(AppState::Menu), cleanup_menu)
             // Game Over
             .add_systems(OnEnter(AppState::GameOver), setup_game_over)
-            .add_systems(Update, game_over.run_if(in_state(AppState::GameOver)))
-            .add_systems(OnExit(AppState::GameOver), cleanup_game_over);
+            .add_systems(OnExit(AppState::GameOver), cleanup_game_over)
+            // Shop
+            .add_systems(OnEnter(AppState::Shop), setup_shop)
+            .add_systems(
+                Update,
+                (shop, refresh_shop)
+                    .chain()
+                    .run_if(in_state(AppState::Shop)),
+            )
+            .add_systems(OnExit(AppState::Shop), cleanup_shop);
     }
 }
@@ -0,0 +1,252 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{Modifier, ModifierOp, Player, PlayerStats, Stat},
+    events::{ButtonHovered, ButtonPressed},
+    profile::Profile,
+    resources::{GameRng, PlayerInput, RoundConfig, RoundParams, RoundPhase},
+    states::{AppState, PlayerInputSet},
+    systems::rounds,
+    ui::{HOVERED_BUTTON, NORMAL_BUTTON, PRESSED_BUTTON},
+};
+
+/// Choices offered at each intermission.
+const DRAFT_SIZE: usize = 3;
+
+/// An upgrade that can be drafted, applied by stacking its modifiers onto `PlayerStats`.
+pub struct Upgrade {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub modifiers: &'static [Modifier],
+}
+
+const fn add(stat: Stat, v: f32) -> Modifier {
+    Modifier {
+        stat,
+        op: ModifierOp::Add(v),
+    }
+}
+
+const fn mul(stat: Stat, v: f32) -> Modifier {
+    Modifier {
+        stat,
+        op: ModifierOp::Mul(v),
+    }
+}
+
+pub const UPGRADES: &[Upgrade] = &[
+    Upgrade {
+        name: "Quick Feet",
+        description: "+15% move speed",
+        modifiers: &[mul(Stat::MoveSpeed, 1.15)],
+    },
+    Upgrade {
+        name: "Vitality",
+        description: "+1 max health",
+        modifiers: &[add(Stat::MaxHealth, 1.0)],
+    },
+    Upgrade {
+        name: "Trigger Happy",
+        description: "+25% fire rate",
+        modifiers: &[mul(Stat::FireRate, 1.25)],
+    },
+    Upgrade {
+        name: "Split Shot",
+        description: "+1 bullet, -10% fire rate",
+        modifiers: &[add(Stat::BulletCount, 1.0), mul(Stat::FireRate, 0.9)],
+    },
+    Upgrade {
+        name: "Piercing Rounds",
+        description: "bullets pass through one more enemy",
+        modifiers: &[add(Stat::BulletPierce, 1.0)],
+    },
+    Upgrade {
+        name: "Iron Skin",
+        description: "+0.5s invulnerability after a hit",
+        modifiers: &[add(Stat::HitCooldown, 0.5)],
+    },
+    Upgrade {
+        name: "Long Barrel",
+        description: "+20% bullet speed, +0.1s bullet range",
+        modifiers: &[mul(Stat::BulletSpeed, 1.2), add(Stat::BulletLifetime, 0.1)],
+    },
+    Upgrade {
+        name: "Glass Cannon",
+        description: "+50% fire rate, -1 max health",
+        modifiers: &[mul(Stat::FireRate, 1.5), add(Stat::MaxHealth, -1.0)],
+    },
+];
+
+/// What the player picked this run, kept for the summary after the player is gone.
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource, Default)]
+pub struct RunSummary {
+    pub picks: Vec<String>,
+    pub stats: PlayerStats,
+}
+
+/// Upgrades on offer as indices into `UPGRADES`, empty outside of an intermission. Saved with the
+/// run so a resumed intermission offers the same draft.
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource, Default)]
+pub struct Draft(pub Vec<usize>);
+
+/// The draft shown during an intermission.
+#[derive(Component)]
+struct UpgradeMenu;
+
+#[derive(Component, Clone, Copy)]
+struct UpgradeButton(usize);
+
+/// A run with no picks yet ends on the stats the profile started it with.
+fn reset_summary(mut commands: Commands, profile: Res<Profile>) {
+    commands.insert_resource(RunSummary {
+        picks: Vec::new(),
+        stats: profile.starting_stats(),
+    });
+    commands.insert_resource(Draft::default());
+}
+
+fn show_upgrades(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    round: Res<RoundParams>,
+    mut rng: ResMut<GameRng>,
+    mut draft: ResMut<Draft>,
+    menu: Query<(), With<UpgradeMenu>>,
+) {
+    if round.phase != RoundPhase::Intermission || !menu.is_empty() {
+        return;
+    }
+    let font = asset_server.load("fonts/Kenney Mini.ttf");
+    // a restored intermission already has its draft
+    if draft.0.is_empty() {
+        draft.0 = rand::seq::index::sample(&mut rng.0, UPGRADES.len(), DRAFT_SIZE).into_vec();
+    }
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            UpgradeMenu,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Pick an upgrade",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                },
+            ));
+            for (i, choice) in draft.0.iter().enumerate() {
+                let upgrade = &UPGRADES[*choice];
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(560.),
+                                height: Val::Px(50.),
+                                margin: UiRect::all(Val::Px(5.)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: NORMAL_BUTTON.into(),
+                            ..default()
+                        },
+                        UpgradeButton(*choice),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            format!("[{}] {}: {}", i + 1, upgrade.name, upgrade.description),
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 24.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+/// Stacks the upgrade picked with a click or through `PlayerInput` and starts the next round.
+#[allow(clippy::too_many_arguments)]
+fn pick_upgrade(
+    mut commands: Commands,
+    input: Res<PlayerInput>,
+    mut round: ResMut<RoundParams>,
+    config: Res<RoundConfig>,
+    mut summary: ResMut<RunSummary>,
+    mut draft: ResMut<Draft>,
+    menu: Query<Entity, With<UpgradeMenu>>,
+    mut buttons: Query<(&Interaction, &UpgradeButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut player: Query<&mut PlayerStats, With<Player>>,
+    mut hovered: EventWriter<ButtonHovered>,
+    mut pressed: EventWriter<ButtonPressed>,
+) {
+    let Ok(menu) = menu.get_single() else {
+        return;
+    };
+    let mut picked = input.pick.and_then(|i| draft.0.get(i)).copied();
+    for (interaction, button, mut color) in &mut buttons {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                picked = Some(button.0);
+            }
+            Interaction::Hovered => {
+                *color = HOVERED_BUTTON.into();
+                hovered.send(ButtonHovered);
+            }
+            Interaction::None => {
+                *color = NORMAL_BUTTON.into();
+            }
+        }
+    }
+    let Some(choice) = picked else {
+        return;
+    };
+    let upgrade = &UPGRADES[choice];
+    pressed.send(ButtonPressed);
+    if let Ok(mut stats) = player.get_single_mut() {
+        stats.modifiers.extend_from_slice(upgrade.modifiers);
+        summary.stats = stats.clone();
+    }
+    summary.picks.push(upgrade.name.to_string());
+    draft.0.clear();
+    commands.entity(menu).despawn_recursive();
+    rounds::next_round(&mut round, &config);
+}
+
+fn cleanup_upgrades(mut commands: Commands, query: Query<Entity, With<UpgradeMenu>>) {
+    for e in &query {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+pub struct UpgradesPlugin;
+
+impl Plugin for UpgradesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunSummary>()
+            .init_resource::<Draft>()
+            .add_systems(OnEnter(AppState::InGame), reset_summary)
+            .add_systems(
+                Update,
+                (show_upgrades, pick_upgrade.after(PlayerInputSet))
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), cleanup_upgrades);
+    }
+}
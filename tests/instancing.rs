@@ -0,0 +1,181 @@
+//! Draws a row of instanced prefabs above a row of regular ones offscreen, with instancing forced
+//! on so both paths are covered even on a GPU-less runner, and checks they look the same.
+use alone::{
+    instancing::{Instancing, InstancingPlugin, INSTANCING_ENV},
+    prefabs,
+};
+use bevy::{
+    audio::AudioPlugin,
+    ecs::system::CommandQueue,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        pipelined_rendering::PipelinedRenderingPlugin,
+        render_asset::RenderAssets,
+        render_resource::{
+            BufferDescriptor, BufferUsages, CachedPipelineState, CommandEncoderDescriptor,
+            Extent3d, ImageCopyBuffer, ImageDataLayout, MapMode, PipelineCache, TextureDescriptor,
+            TextureDimension, TextureFormat, TextureUsages,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        texture::BevyDefault,
+        RenderApp,
+    },
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+
+const FRAMES: usize = 20;
+/// Width and height of the render target, in pixels and world units alike.
+const SIZE: u32 = 64;
+/// The instanced row is drawn this far above the middle, the regular one as far below.
+const ROW_Y: f32 = 12.0;
+
+fn build_app() -> (App, Handle<Image>) {
+    let mut app = App::new();
+    app.add_plugins((
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            })
+            .disable::<WinitPlugin>()
+            .disable::<AudioPlugin>()
+            // keeps the render world around to look into
+            .disable::<PipelinedRenderingPlugin>(),
+        InstancingPlugin,
+    ));
+    // what the winit runner does before the first frame, the adapter is picked off the main thread
+    while !app.ready() {
+        bevy::tasks::tick_global_task_pools_on_main_thread();
+    }
+    app.finish();
+    app.cleanup();
+
+    let size = Extent3d {
+        width: SIZE,
+        height: SIZE,
+        ..default()
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::bevy_default(),
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::COPY_SRC
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    image.resize(size);
+    let target = app.world.resource_mut::<Assets<Image>>().add(image);
+    app.world.spawn(Camera2dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(target.clone()),
+            ..default()
+        },
+        ..default()
+    });
+
+    (app, target)
+}
+
+/// Spawns a row of prefabs drawn the way `instancing` says.
+fn spawn_row(app: &mut App, instancing: &Instancing, y: f32) {
+    let mesh = app
+        .world
+        .resource_mut::<Assets<Mesh>>()
+        .add(shape::Quad::new(Vec2::splat(6.0)).into());
+    let material = app
+        .world
+        .resource_mut::<Assets<ColorMaterial>>()
+        .add(Color::RED.into());
+    let mut queue = CommandQueue::default();
+    let mut commands = Commands::new(&mut queue, &app.world);
+    for i in 0..6 {
+        let transform = Transform::from_xyz(i as f32 * 10.0 - 25.0, y, i as f32);
+        let mut entity = commands.spawn_empty();
+        prefabs::insert_visuals(&mut entity, instancing, &mesh, material.clone(), transform);
+    }
+    queue.apply(&mut app.world);
+}
+
+/// Renders a few frames, making sure no pipeline failed to build.
+fn render(app: &mut App) {
+    for _ in 0..FRAMES {
+        app.update();
+    }
+    let cache = app.sub_app(RenderApp).world.resource::<PipelineCache>();
+    for pipeline in cache.pipelines() {
+        if let CachedPipelineState::Err(e) = &pipeline.state {
+            panic!("a pipeline failed to build: {e}");
+        }
+    }
+}
+
+/// Copies the render target back, as rows of RGBA pixels from the top.
+fn read_back(app: &App, target: &Handle<Image>) -> Vec<u8> {
+    let world = &app.sub_app(RenderApp).world;
+    let device = world.resource::<RenderDevice>();
+    let image = world
+        .resource::<RenderAssets<Image>>()
+        .get(target)
+        .expect("the target should be on the GPU");
+    let buffer = device.create_buffer(&BufferDescriptor {
+        label: None,
+        size: (SIZE * SIZE * 4) as u64,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor::default());
+    encoder.copy_texture_to_buffer(
+        image.texture.as_image_copy(),
+        ImageCopyBuffer {
+            buffer: &buffer,
+            layout: ImageDataLayout {
+                offset: 0,
+                // a row of 64 pixels is exactly the 256 bytes copies are aligned to
+                bytes_per_row: Some(SIZE * 4),
+                rows_per_image: None,
+            },
+        },
+        image.texture.size(),
+    );
+    world.resource::<RenderQueue>().submit([encoder.finish()]);
+    let slice = buffer.slice(..);
+    slice.map_async(MapMode::Read, |mapped| mapped.expect("the copy should map"));
+    device.poll(wgpu::Maintain::Wait);
+    let pixels = slice.get_mapped_range().to_vec();
+    pixels
+}
+
+#[test]
+fn both_paths_draw_the_same() {
+    std::env::set_var(INSTANCING_ENV, "1");
+    let (mut app, target) = build_app();
+    assert!(app.world.resource::<Instancing>().enabled);
+    spawn_row(&mut app, &Instancing { enabled: true }, ROW_Y);
+    spawn_row(&mut app, &Instancing { enabled: false }, -ROW_Y);
+    render(&mut app);
+    let pixels = read_back(&app, &target);
+
+    let row_bytes = SIZE as usize * 4;
+    let shift = 2 * ROW_Y as usize * row_bytes;
+    let mid = SIZE as usize / 2;
+    let upper = &pixels[(mid - 2 * ROW_Y as usize) * row_bytes..mid * row_bytes];
+    let lower = &pixels[(mid - 2 * ROW_Y as usize) * row_bytes + shift..mid * row_bytes + shift];
+    let red = upper
+        .chunks(4)
+        .filter(|p| p[0] > 200 && p[1] < 50 && p[2] < 50)
+        .count();
+    // six quads of six by six pixels
+    assert_eq!(red, 6 * 36, "the instanced row should be drawn");
+    assert_eq!(upper, lower, "both rows should look the same");
+}
@@ -0,0 +1,43 @@
+//! Profiles written by older builds keep loading.
+use alone::profile::{Perk, Profile, WeaponKind, PROFILE_VERSION};
+
+/// As saved before profiles carried a version.
+const V0: &str = r#"(
+    currency: 120,
+    runs: 4,
+    best_score: 900,
+    weapons: [Pistol, Railgun],
+    perks: {Vitality: 2},
+    loadout: (weapon: Railgun),
+)"#;
+
+#[test]
+fn migrates_v0() {
+    let profile = Profile::from_ron(V0).expect("a v0 profile should load");
+    assert_eq!(profile.version, PROFILE_VERSION);
+    assert_eq!(profile.currency, 120);
+    assert_eq!(profile.weapons, [WeaponKind::Pistol, WeaponKind::Railgun]);
+    assert_eq!(profile.perk_level(Perk::Vitality), 2);
+    assert_eq!(profile.loadout.weapon, WeaponKind::Railgun);
+}
+
+#[test]
+fn round_trips_current() {
+    let mut profile = Profile {
+        currency: 35,
+        best_score: 1200,
+        ..Default::default()
+    };
+    profile.weapons.push(WeaponKind::SpreadGun);
+    profile.perks.insert(Perk::RapidFire, 3);
+    profile.loadout.weapon = WeaponKind::SpreadGun;
+    let text = ron::ser::to_string(&profile).unwrap();
+    let loaded = Profile::from_ron(&text).expect("a saved profile should load");
+    assert_eq!(ron::ser::to_string(&loaded).unwrap(), text);
+}
+
+#[test]
+fn rejects_newer() {
+    let text = format!("(version: {})", PROFILE_VERSION + 1);
+    assert!(Profile::from_ron(&text).is_err());
+}
@@ -0,0 +1,51 @@
+//! The shipped sheets load and every clip stays on its sheet.
+use alone::{
+    components::EnemyArchetype,
+    sprites::{Clip, SpriteSheets, SpriteSheetsLoader, DEFAULT_SPRITES},
+};
+use bevy::{asset::LoadState, prelude::*};
+
+#[test]
+fn default_sheets_load() {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .add_asset::<Image>()
+        .add_asset::<TextureAtlas>()
+        .add_asset::<SpriteSheets>()
+        .init_asset_loader::<SpriteSheetsLoader>();
+    let handle: Handle<SpriteSheets> = app.world.resource::<AssetServer>().load(DEFAULT_SPRITES);
+    for _ in 0..1000 {
+        app.update();
+        if app
+            .world
+            .resource::<Assets<SpriteSheets>>()
+            .contains(&handle)
+        {
+            break;
+        }
+        let state = app.world.resource::<AssetServer>().get_load_state(&handle);
+        assert_ne!(state, LoadState::Failed, "{DEFAULT_SPRITES} failed to load");
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    let sheets = app.world.resource::<Assets<SpriteSheets>>();
+    let sheets = sheets.get(&handle).expect("the sheets should load");
+
+    let player = sheets
+        .player
+        .as_ref()
+        .expect("the player should have a sheet");
+    let grunt = &sheets.enemies[&EnemyArchetype::Grunt];
+    assert!(grunt.def.clips.contains_key(&Clip::Death));
+    for sheet in std::iter::once(player).chain(sheets.enemies.values()) {
+        let tiles = sheet.def.columns * sheet.def.rows;
+        assert!(sheet.def.clips.contains_key(&Clip::Idle));
+        for clip in sheet.def.clips.values() {
+            assert!(!clip.frames.is_empty());
+            assert!(
+                clip.frames.iter().all(|&i| i < tiles),
+                "{}",
+                sheet.def.image
+            );
+        }
+    }
+}